@@ -1,20 +1,138 @@
+/// Picks between two values from a condition, like `props.online.choose("Online", "Offline")`.
+/// Options and results choose the first value when they hold `Some` or `Ok`.
 pub trait Choosable<T> {
     fn choose(&self, if_true: T, if_false: T) -> T;
 
-    fn choose_with<F>(&self, if_true: F, if_false: F) -> T
+    /// Calls one of the closures, which can be of different types. The closures of
+    /// options and results receive the value they hold, like `Some(x)` or `Err(e)`.
+    fn choose_with<F, G>(&self, if_true: F, if_false: G) -> T
     where
-        F: FnOnce() -> T;
+        Self: ChooseWith<T, F, G>,
+    {
+        self.call_choice(if_true, if_false)
+    }
+}
+
+/// Closures that `choose_with` accepts for each `Choosable` type: closures without
+/// arguments for `bool`, and closures receiving the held value for `Option` and `Result`.
+pub trait ChooseWith<T, F, G> {
+    fn call_choice(&self, if_true: F, if_false: G) -> T;
 }
 
 impl<T> Choosable<T> for bool {
     fn choose(&self, if_true: T, if_false: T) -> T {
         if *self { if_true } else { if_false }
     }
+}
 
-    fn choose_with<F>(&self, if_true: F, if_false: F) -> T
-    where
-        F: FnOnce() -> T,
-    {
+impl<T, F, G> ChooseWith<T, F, G> for bool
+where
+    F: FnOnce() -> T,
+    G: FnOnce() -> T,
+{
+    fn call_choice(&self, if_true: F, if_false: G) -> T {
         if *self { if_true() } else { if_false() }
     }
 }
+
+impl<T, U> Choosable<T> for Option<U> {
+    fn choose(&self, if_some: T, if_none: T) -> T {
+        if self.is_some() { if_some } else { if_none }
+    }
+}
+
+impl<T, U, F, G> ChooseWith<T, F, G> for Option<U>
+where
+    F: FnOnce(&U) -> T,
+    G: FnOnce() -> T,
+{
+    fn call_choice(&self, if_some: F, if_none: G) -> T {
+        match self {
+            Some(value) => if_some(value),
+            None => if_none(),
+        }
+    }
+}
+
+impl<T, U, E> Choosable<T> for Result<U, E> {
+    fn choose(&self, if_ok: T, if_err: T) -> T {
+        if self.is_ok() { if_ok } else { if_err }
+    }
+}
+
+impl<T, U, E, F, G> ChooseWith<T, F, G> for Result<U, E>
+where
+    F: FnOnce(&U) -> T,
+    G: FnOnce(&E) -> T,
+{
+    fn call_choice(&self, if_ok: F, if_err: G) -> T {
+        match self {
+            Ok(value) => if_ok(value),
+            Err(err) => if_err(err),
+        }
+    }
+}
+
+/// Picks the value of the first case whose label equals `value`, or `otherwise` when
+/// none does:
+///
+/// ```
+/// let role = String::from("editor");
+/// let label = magik::select(&role, [("admin", "Administrator"), ("editor", "Editor")], "Reader");
+/// assert_eq!(label, "Editor");
+/// ```
+pub fn select<V, K, T>(value: &V, cases: impl IntoIterator<Item = (K, T)>, otherwise: T) -> T
+where
+    V: PartialEq<K> + ?Sized,
+{
+    cases
+        .into_iter()
+        .find(|(label, _)| value == label)
+        .map_or(otherwise, |(_, chosen)| chosen)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Choosable, select};
+
+    #[test]
+    fn test_choose_with_different_closures() {
+        let name = String::from("Ada");
+        assert_eq!(
+            true.choose_with(|| name.clone(), || format!("not {}", name)),
+            "Ada"
+        );
+        assert_eq!(false.choose("yes", "no"), "no");
+    }
+
+    #[test]
+    fn test_choose_option_and_result() {
+        let user = Some("Ada");
+        assert_eq!(user.choose_with(|name| name.len(), || 0), 3);
+        assert_eq!(None::<&str>.choose("Member", "Guest"), "Guest");
+
+        let count: Result<u32, String> = Err("timeout".into());
+        assert_eq!(
+            count.choose_with(|count| count.to_string(), |err| format!("Error: {}", err)),
+            "Error: timeout"
+        );
+        assert_eq!(Ok::<_, ()>(1).choose("ok", "failed"), "ok");
+    }
+
+    #[test]
+    fn test_select() {
+        #[derive(PartialEq)]
+        enum Status {
+            Active,
+            Suspended,
+            Closed,
+        }
+
+        let cases = [(Status::Active, "🟢"), (Status::Suspended, "🔴")];
+        assert_eq!(select(&Status::Suspended, cases, "⚪"), "🔴");
+        assert_eq!(
+            select(&Status::Closed, [(Status::Active, "🟢")], "⚪"),
+            "⚪"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::{__private::HtmlContext, Escape, Parser, Renderable, TemplateData};
+
+/// Field of a template struct, rendered by the hot-reloaded template when its type
+/// implements `Renderable`. Which of `RenderableField` or `OpaqueField` applies is
+/// decided by method resolution, as in `(&Field(&self.title)).as_renderable()`.
+pub struct Field<'a, T: ?Sized>(pub &'a T);
+
+pub trait RenderableField<'a> {
+    fn as_renderable(&self) -> Option<&'a dyn Renderable>;
+}
+
+impl<'a, T: Renderable> RenderableField<'a> for Field<'a, T> {
+    fn as_renderable(&self) -> Option<&'a dyn Renderable> {
+        Some(self.0)
+    }
+}
+
+pub trait OpaqueField<'a> {
+    fn as_renderable(&self) -> Option<&'a dyn Renderable>;
+}
+
+impl<'a, T: ?Sized> OpaqueField<'a> for &Field<'a, T> {
+    fn as_renderable(&self) -> Option<&'a dyn Renderable> {
+        None
+    }
+}
+
+/// Template file re-read on each render in development builds, together with what
+/// is needed to interpret it without recompiling.
+pub struct Reload<'a> {
+    pub path: &'a str,
+    pub delimiters: (&'a str, &'a str),
+    /// Name of the context variable, like `props`
+    pub context: &'a str,
+    pub escape: Escape,
+    /// Whether the compiled template removes the lines of statement blocks
+    pub trim_blocks: bool,
+}
+
+impl Reload<'_> {
+    /// Renders the current contents of the template file, reading the fields of the
+    /// struct through `lookup`. Returns `None`, after a warning, when the template uses
+    /// anything other than `props.field` blocks, so the compiled version renders instead.
+    pub fn render<'f>(
+        &self,
+        lookup: &dyn Fn(&str) -> Option<&'f dyn Renderable>,
+        out: &mut dyn fmt::Write,
+    ) -> Option<fmt::Result> {
+        match self.interpret(lookup) {
+            Ok(output) => Some(out.write_str(&output)),
+            Err(reason) => {
+                warn_once(format!(
+                    "magik: cannot reload {}: {}, rendering the compiled template",
+                    self.path, reason
+                ));
+                None
+            }
+        }
+    }
+
+    fn interpret<'f>(
+        &self,
+        lookup: &dyn Fn(&str) -> Option<&'f dyn Renderable>,
+    ) -> Result<String, String> {
+        let source = std::fs::read_to_string(self.path).map_err(|err| err.to_string())?;
+        let (open, close) = self.delimiters;
+        let mut output = String::with_capacity(source.len());
+        // As in the compiled template, HTML values are escaped for their position
+        let mut context = (self.escape == Escape::Html).then(HtmlContext::default);
+
+        for fragment in Parser::new(&source).with_delimiters(open, close) {
+            match fragment.map_err(|err| err.to_string())? {
+                TemplateData::String(text) => {
+                    if let Some(context) = &mut context {
+                        context.feed(&text);
+                    }
+                    output.push_str(&text);
+                }
+                TemplateData::Code(code) => {
+                    let inner = code
+                        .strip_prefix('{')
+                        .and_then(|code| code.strip_suffix('}'))
+                        .unwrap_or(&code)
+                        .trim();
+                    if inner.is_empty() {
+                        // The compiled template would remove the line of the block
+                        if self.trim_blocks {
+                            return Err("empty blocks are trimmed by `trim_blocks`".into());
+                        }
+                        continue;
+                    }
+
+                    let field = inner
+                        .strip_prefix(self.context)
+                        .and_then(|rest| rest.strip_prefix('.'))
+                        .filter(|field| is_identifier(field))
+                        .ok_or_else(|| {
+                            format!("`{}` is not a `{}.field` path", inner, self.context)
+                        })?;
+                    let value = lookup(field)
+                        .ok_or_else(|| format!("field `{}` is not renderable", field))?;
+
+                    let escape = match &context {
+                        Some(context) => context.escape()?,
+                        None => self.escape,
+                    };
+                    value
+                        .render_escaped_to(&mut output, escape)
+                        .map_err(|_| format!("field `{}` failed to render", field))?;
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_')
+        && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+/// Prints a warning the first time it happens, since templates render repeatedly.
+fn warn_once(message: String) {
+    static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+    let mut warned = WARNED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner());
+    if warned.insert(message.clone()) {
+        eprintln!("{}", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Page {
+        title: String,
+        count: u32,
+        items: Vec<Vec<u8>>,
+    }
+
+    impl Page {
+        // Like the generated lookup, the borrow selects `OpaqueField` for other types
+        #[allow(clippy::needless_borrow)]
+        fn lookup(&self, name: &str) -> Option<&dyn Renderable> {
+            match name {
+                "title" => (&Field(&self.title)).as_renderable(),
+                "count" => (&Field(&self.count)).as_renderable(),
+                "items" => (&Field(&self.items)).as_renderable(),
+                _ => None,
+            }
+        }
+    }
+
+    fn reload(path: &str, template: &str, page: &Page) -> Option<String> {
+        std::fs::write(path, template).unwrap();
+
+        let reload = Reload {
+            path,
+            delimiters: ("{{", "}}"),
+            context: "props",
+            escape: Escape::Html,
+            trim_blocks: false,
+        };
+        let mut output = String::new();
+        let result = reload.render(&|name| page.lookup(name), &mut output);
+        std::fs::remove_file(path).unwrap();
+
+        result.map(|result| result.map(|_| output).unwrap())
+    }
+
+    #[test]
+    fn test_reload_fields() {
+        let path = std::env::temp_dir().join(format!("magik-reload-{}.tmp", std::process::id()));
+        let page = Page {
+            title: "<Home>".into(),
+            count: 3,
+            items: vec![],
+        };
+
+        assert_eq!(
+            reload(
+                path.to_str().unwrap(),
+                "<h1>{{ props.title }}</h1> {{props.count}}",
+                &page
+            )
+            .as_deref(),
+            Some("<h1>&lt;Home&gt;</h1> 3")
+        );
+        assert_eq!(
+            reload(path.to_str().unwrap(), "{{ props.count + 1 }}", &page),
+            None
+        );
+        assert_eq!(
+            reload(path.to_str().unwrap(), "{{ props.missing }}", &page),
+            None
+        );
+        assert_eq!(
+            reload(path.to_str().unwrap(), "<a href={{ props.title }}>", &page),
+            None
+        );
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_field_dispatch() {
+        struct Opaque;
+
+        assert!((&Field(&1)).as_renderable().is_some());
+        assert!((&Field(&Opaque)).as_renderable().is_none());
+    }
+}
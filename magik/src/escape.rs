@@ -0,0 +1,58 @@
+use std::fmt;
+
+use crate::{Escape, Renderable};
+
+/// Wrapper for trusted values that must be inserted without escaping.
+///
+/// ```
+/// use magik::{Escape, Raw, Renderable};
+///
+/// let markup = Raw("<b>bold</b>");
+/// assert_eq!(markup.render_escaped(Escape::Html), "<b>bold</b>");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Raw<T>(pub T);
+
+impl<T: Renderable> Renderable for Raw<T> {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.0.render_to(out)
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, _escape: Escape) -> fmt::Result {
+        self.0.render_to(out)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.size_hint()
+    }
+
+    fn content_type(&self) -> Option<&'static str> {
+        self.0.content_type()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Raw;
+    use crate::{Escape, Renderable};
+
+    #[test]
+    fn test_render_url_as_a_whole() {
+        assert_eq!(("java", "script:x").render_escaped(Escape::Url), "#");
+        assert_eq!(
+            Raw("javascript:x").render_escaped(Escape::Url),
+            "javascript:x"
+        );
+    }
+
+    #[test]
+    fn test_render_escaped() {
+        assert_eq!("<b>".render_escaped(Escape::Html), "&lt;b&gt;");
+        assert_eq!(Raw("<b>").render_escaped(Escape::Html), "<b>");
+        assert_eq!(
+            vec!["<i>", "ok"].render_escaped(Escape::Html),
+            "&lt;i&gt;\nok"
+        );
+        assert_eq!(Some("&").render_escaped(Escape::Html), "&amp;");
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const WEEKDAYS: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// Seconds since the Unix epoch of a time, negative for times before it.
+pub(super) fn unix_seconds(time: &SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_secs() as i64,
+        Err(err) => -(err.duration().as_secs_f64().ceil() as i64),
+    }
+}
+
+/// Formats a Unix timestamp in UTC with `strftime`-like specifiers: `%Y`, `%y`, `%m`,
+/// `%d`, `%e`, `%H`, `%M`, `%S`, `%B`, `%b`, `%A`, `%a` and `%%`. Other specifiers are
+/// left as they are.
+pub(super) fn format_date(seconds: i64, format: &str) -> String {
+    let days = seconds.div_euclid(86_400);
+    let time = seconds.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let weekday = WEEKDAYS[(days + 4).rem_euclid(7) as usize];
+    let month_name = MONTHS[month as usize - 1];
+
+    let mut output = String::with_capacity(format.len() + 8);
+    let mut chars = format.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            output.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            Some('Y') => output.push_str(&year.to_string()),
+            Some('y') => output.push_str(&format!("{:02}", year.rem_euclid(100))),
+            Some('m') => output.push_str(&format!("{:02}", month)),
+            Some('d') => output.push_str(&format!("{:02}", day)),
+            Some('e') => output.push_str(&day.to_string()),
+            Some('H') => output.push_str(&format!("{:02}", time / 3600)),
+            Some('M') => output.push_str(&format!("{:02}", time % 3600 / 60)),
+            Some('S') => output.push_str(&format!("{:02}", time % 60)),
+            Some('B') => output.push_str(month_name),
+            Some('b') => output.push_str(&month_name[..3]),
+            Some('A') => output.push_str(weekday),
+            Some('a') => output.push_str(&weekday[..3]),
+            Some('%') => output.push('%'),
+            Some(other) => {
+                output.push('%');
+                output.push(other);
+            }
+            None => output.push('%'),
+        }
+    }
+
+    output
+}
+
+/// Converts days since the Unix epoch into a `(year, month, day)` date of the
+/// proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0, "%Y-%m-%d %H:%M:%S"), "1970-01-01 00:00:00");
+        assert_eq!(
+            format_date(1_709_210_096, "%a %e %b %Y, %H:%M"),
+            "Thu 29 Feb 2024, 12:34"
+        );
+        assert_eq!(
+            format_date(-86_400, "%A %d %B %y"),
+            "Wednesday 31 December 69"
+        );
+        assert_eq!(format_date(0, "100%% %Q"), "100% %Q");
+    }
+}
@@ -0,0 +1,414 @@
+//! Filters applied to interpolated values with the `|` syntax of templates, as in
+//! `{{ props.name | trim | upper | truncate(20) }}`.
+//!
+//! A filter `name(args)` is a call to the method `name` on the value, so each filter is
+//! a method of a trait. The traits of this module are in scope in every filter pipeline,
+//! and a crate adds its own filters with a trait of its own, brought into scope where the
+//! template is declared. Their names are listed in the `filters` option of the template
+//! or of the crate configuration, so that other uses of `|` stay bitwise ors:
+//!
+//! ```ignore
+//! pub trait CurrencyFilter {
+//!     fn currency(&self) -> String;
+//! }
+//!
+//! impl CurrencyFilter for f64 {
+//!     fn currency(&self) -> String {
+//!         format!("${:.2}", self)
+//!     }
+//! }
+//!
+//! // #[template(source = "{{ props.total | currency }}", filters = (currency))]
+//! ```
+
+mod date;
+
+use std::{
+    fmt::{self, Display},
+    time::SystemTime,
+};
+
+use crate::{
+    Escape, Renderable, escape_html,
+    runtime::{ToValue, Value},
+};
+
+/// Filters for any value that implements `Display`, applied to its text.
+pub trait TextFilters: Display {
+    fn upper(&self) -> String {
+        self.to_string().to_uppercase()
+    }
+
+    fn lower(&self) -> String {
+        self.to_string().to_lowercase()
+    }
+
+    /// Uppercases the first character.
+    fn capitalize(&self) -> String {
+        let text = self.to_string();
+        let mut chars = text.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => text,
+        }
+    }
+
+    /// Uppercases the first character of each word.
+    fn title(&self) -> String {
+        let mut output = String::new();
+        let mut word_start = true;
+        for ch in self.to_string().chars() {
+            if word_start {
+                output.extend(ch.to_uppercase());
+            } else {
+                output.push(ch);
+            }
+            word_start = ch.is_whitespace();
+        }
+        output
+    }
+
+    fn trim(&self) -> String {
+        str::trim(&self.to_string()).to_string()
+    }
+
+    /// Keeps the first `length` characters, followed by `...` when the text was longer.
+    fn truncate(&self, length: usize) -> String {
+        let text = self.to_string();
+        match text.char_indices().nth(length) {
+            Some((end, _)) => format!("{}...", &text[..end]),
+            None => text,
+        }
+    }
+
+    /// Indents every line but the first by `width` spaces, for text inserted in an
+    /// already indented line.
+    fn indent(&self, width: usize) -> String {
+        let padding = " ".repeat(width);
+        let text = self.to_string();
+        let mut lines = text.split('\n');
+        let mut output = lines.next().unwrap_or_default().to_string();
+        for line in lines {
+            output.push('\n');
+            if !line.is_empty() {
+                output.push_str(&padding);
+            }
+            output.push_str(line);
+        }
+        output
+    }
+
+    /// Breaks the text into lines of at most `width` characters, between words.
+    fn wordwrap(&self, width: usize) -> String {
+        let text = self.to_string();
+        let mut output = String::with_capacity(text.len());
+        for (index, paragraph) in text.split('\n').enumerate() {
+            if index > 0 {
+                output.push('\n');
+            }
+
+            let mut line_len = 0;
+            for word in paragraph.split_whitespace() {
+                let word_len = word.chars().count();
+                if line_len > 0 && line_len + 1 + word_len > width {
+                    output.push('\n');
+                    line_len = 0;
+                } else if line_len > 0 {
+                    output.push(' ');
+                    line_len += 1;
+                }
+                output.push_str(word);
+                line_len += word_len;
+            }
+        }
+        output
+    }
+
+    /// Percent-encodes every byte but the unreserved characters of URLs.
+    fn urlencode(&self) -> String {
+        let text = self.to_string();
+        let mut output = String::with_capacity(text.len());
+        for byte in text.bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                    output.push(byte as char)
+                }
+                _ => output.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        output
+    }
+
+    /// Escapes the text for HTML, even in templates that don't escape their values.
+    /// Templates that do escape it once, for its position in the markup.
+    fn escape(&self) -> Escaped {
+        Escaped(self.to_string())
+    }
+}
+
+impl<T: Display + ?Sized> TextFilters for T {}
+
+/// Filters for numbers.
+pub trait NumberFilters: Display {
+    /// Separates the thousands of the integer part with commas, like `1,234,567.5`.
+    fn number(&self) -> String {
+        let text = self.to_string();
+        let (sign, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", text.as_str()),
+        };
+        let (integer, fraction) = match unsigned.find('.') {
+            Some(dot) => unsigned.split_at(dot),
+            None => (unsigned, ""),
+        };
+
+        let mut output = String::with_capacity(text.len() + integer.len() / 3);
+        output.push_str(sign);
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                output.push(',');
+            }
+            output.push(digit);
+        }
+        output.push_str(fraction);
+        output
+    }
+
+    /// Picks the singular word for a count of one and the plural one otherwise, as in
+    /// `{{ props.count }} {{ props.count | pluralize("item", "items") }}`.
+    fn pluralize<'a>(&self, singular: &'a str, plural: &'a str) -> &'a str {
+        if self.to_string() == "1" {
+            singular
+        } else {
+            plural
+        }
+    }
+}
+
+macro_rules! impl_number_filters {
+    ($($type:ty),+ $(,)?) => {
+        $(impl NumberFilters for $type {})+
+    };
+}
+
+impl_number_filters!(
+    u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64,
+);
+
+/// Formats dates in UTC with `strftime`-like specifiers, as in
+/// `{{ props.created_at | date("%d %b %Y") }}`. Integers are read as Unix timestamps.
+pub trait DateFilters {
+    fn date(&self, format: &str) -> String;
+}
+
+impl DateFilters for SystemTime {
+    fn date(&self, format: &str) -> String {
+        date::format_date(date::unix_seconds(self), format)
+    }
+}
+
+impl DateFilters for i64 {
+    fn date(&self, format: &str) -> String {
+        date::format_date(*self, format)
+    }
+}
+
+/// Replaces missing values, `None` or empty strings, with a fallback, as in
+/// `{{ props.nickname | default("Anonymous") }}`.
+pub trait DefaultFilter {
+    type Value: Renderable + ?Sized;
+
+    fn default<D: Renderable>(&self, fallback: D) -> Fallback<'_, Self::Value, D>;
+}
+
+impl<T: Renderable> DefaultFilter for Option<T> {
+    type Value = T;
+
+    fn default<D: Renderable>(&self, fallback: D) -> Fallback<'_, T, D> {
+        Fallback {
+            value: self.as_ref(),
+            fallback,
+        }
+    }
+}
+
+impl DefaultFilter for str {
+    type Value = str;
+
+    fn default<D: Renderable>(&self, fallback: D) -> Fallback<'_, str, D> {
+        Fallback {
+            value: Some(self).filter(|value| !value.is_empty()),
+            fallback,
+        }
+    }
+}
+
+impl DefaultFilter for String {
+    type Value = str;
+
+    fn default<D: Renderable>(&self, fallback: D) -> Fallback<'_, str, D> {
+        self.as_str().default(fallback)
+    }
+}
+
+/// Value of the `escape` filter, never rendered without escaping.
+pub struct Escaped(String);
+
+impl Renderable for Escaped {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        out.write_str(&escape_html(&self.0))
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        match escape {
+            Escape::None => self.render_to(out),
+            escape => self.0.render_escaped_to(out, escape),
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Value of the `default` filter, rendering the fallback when the value is missing.
+pub struct Fallback<'a, T: ?Sized, D> {
+    value: Option<&'a T>,
+    fallback: D,
+}
+
+impl<T: Renderable + ?Sized, D: Renderable> Renderable for Fallback<'_, T, D> {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self.value {
+            Some(value) => value.render_to(out),
+            None => self.fallback.render_to(out),
+        }
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        match self.value {
+            Some(value) => value.render_escaped_to(out, escape),
+            None => self.fallback.render_escaped_to(out, escape),
+        }
+    }
+}
+
+/// Encodes values convertible into runtime values as JSON, like the fields of a
+/// struct deriving `MagikContext`.
+pub trait JsonFilter {
+    fn json(&self) -> String;
+}
+
+impl<T: ToValue + ?Sized> JsonFilter for T {
+    fn json(&self) -> String {
+        let mut output = String::new();
+        write_json(&self.to_value(), &mut output);
+        output
+    }
+}
+
+fn write_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        Value::Int(value) => out.push_str(&value.to_string()),
+        Value::Float(value) if value.is_finite() => out.push_str(&value.to_string()),
+        Value::Float(_) => out.push_str("null"),
+        Value::String(value) => write_json_string(value, out),
+        Value::List(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_json(item, out);
+            }
+            out.push(']');
+        }
+        Value::Map(entries) => {
+            out.push('{');
+            for (index, (key, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_json_string(key, out);
+                out.push(':');
+                write_json(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_json_string(value: &str, out: &mut String) {
+    out.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ch if ch < ' ' => out.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => out.push(ch),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Context;
+
+    #[test]
+    fn test_text_filters() {
+        assert_eq!("hello world".upper(), "HELLO WORLD");
+        assert_eq!("hello world".title(), "Hello World");
+        assert_eq!("émile".capitalize(), "Émile");
+        assert_eq!(TextFilters::trim("  padded "), "padded");
+        assert_eq!("abcdef".truncate(3), "abc...");
+        assert_eq!("abc".truncate(3), "abc");
+        assert_eq!("a\nb\n\nc".indent(2), "a\n  b\n\n  c");
+        assert_eq!("one two three four".wordwrap(9), "one two\nthree\nfour");
+        assert_eq!("a b&c/é".urlencode(), "a%20b%26c%2F%C3%A9");
+        assert_eq!("<b>".escape().render(), "&lt;b&gt;");
+        assert_eq!("<b>".escape().render_escaped(Escape::Html), "&lt;b&gt;");
+        assert_eq!("javascript:x".escape().render_escaped(Escape::Url), "#");
+    }
+
+    #[test]
+    fn test_number_filters() {
+        assert_eq!(1234567.number(), "1,234,567");
+        assert_eq!((-1234.5).number(), "-1,234.5");
+        assert_eq!(999u16.number(), "999");
+        assert_eq!(1.pluralize("item", "items"), "item");
+        assert_eq!(0.pluralize("item", "items"), "items");
+    }
+
+    #[test]
+    fn test_date_filter() {
+        assert_eq!(86_400i64.date("%Y-%m-%d"), "1970-01-02");
+        assert_eq!(SystemTime::UNIX_EPOCH.date("%H:%M"), "00:00");
+    }
+
+    #[test]
+    fn test_default_filter() {
+        assert_eq!(None::<&str>.default("Anonymous").render(), "Anonymous");
+        assert_eq!(Some("Ada").default("Anonymous").render(), "Ada");
+        assert_eq!(String::new().default("-").render(), "-");
+    }
+
+    #[test]
+    fn test_json_filter() {
+        let context = Context::new()
+            .with("name", "Ada \"The\" Countess")
+            .with("tags", vec![1, 2])
+            .with("admin", None::<bool>);
+        assert_eq!(
+            context.json(),
+            r#"{"admin":null,"name":"Ada \"The\" Countess","tags":[1,2]}"#
+        );
+        assert_eq!(1.5.json(), "1.5");
+    }
+}
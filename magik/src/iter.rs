@@ -0,0 +1,179 @@
+use std::fmt;
+
+use crate::{Escape, Renderable};
+
+/// Items of an iterator rendered one after the other with a separator between them,
+/// built with [`join`]. The iterator is cloned each time it renders, so nothing is
+/// collected in between.
+#[derive(Clone)]
+pub struct Join<I, S> {
+    items: I,
+    separator: S,
+}
+
+/// Renders the items separated by `separator`, like `join(&props.tags, ", ")`.
+pub fn join<I, S>(items: I, separator: S) -> Join<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Renderable,
+    S: Renderable,
+{
+    Join { items, separator }
+}
+
+impl<I, S> Renderable for Join<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Renderable,
+    S: Renderable,
+{
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.render_escaped_to(out, Escape::None)
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        // The scheme of a URL is checked on the joined items
+        if escape == Escape::Url {
+            return out.write_str(&escape.escape(&self.render()));
+        }
+
+        for (index, item) in self.items.clone().into_iter().enumerate() {
+            if index > 0 {
+                self.separator.render_escaped_to(out, escape)?;
+            }
+            item.render_escaped_to(out, escape)?;
+        }
+        Ok(())
+    }
+}
+
+/// Position of the current item of [`each`] in the iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoopInfo {
+    /// Index of the item, starting at 0
+    pub index: usize,
+    pub first: bool,
+    pub last: bool,
+}
+
+impl LoopInfo {
+    /// Checks if the index is even, like the first, third and fifth rows of a table.
+    pub fn is_even(&self) -> bool {
+        self.index.is_multiple_of(2)
+    }
+
+    pub fn is_odd(&self) -> bool {
+        !self.is_even()
+    }
+}
+
+/// Items of an iterator rendered through a closure, built with [`each`].
+#[derive(Clone)]
+pub struct Each<I, F> {
+    items: I,
+    render: F,
+}
+
+/// Renders the value returned by `render` for each item, which also receives the
+/// position of the item:
+///
+/// ```
+/// use magik::Renderable;
+///
+/// let rows = magik::each(["a", "b"], |item, info| {
+///     (info.is_even().then_some("even "), item, (!info.last).then_some(", "))
+/// });
+/// assert_eq!(rows.render(), "even a, b");
+/// ```
+pub fn each<I, F, R>(items: I, render: F) -> Each<I, F>
+where
+    I: IntoIterator + Clone,
+    F: Fn(I::Item, LoopInfo) -> R,
+    R: Renderable,
+{
+    Each { items, render }
+}
+
+impl<I, F, R> Renderable for Each<I, F>
+where
+    I: IntoIterator + Clone,
+    F: Fn(I::Item, LoopInfo) -> R,
+    R: Renderable,
+{
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.render_escaped_to(out, Escape::None)
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        if escape == Escape::Url {
+            return out.write_str(&escape.escape(&self.render()));
+        }
+
+        let mut items = self.items.clone().into_iter().enumerate().peekable();
+
+        while let Some((index, item)) = items.next() {
+            let info = LoopInfo {
+                index,
+                first: index == 0,
+                last: items.peek().is_none(),
+            };
+            (self.render)(item, info).render_escaped_to(out, escape)?;
+        }
+        Ok(())
+    }
+}
+
+/// Conversion into a `Renderable` value, implemented for any iterable of renderable
+/// items, like `props.tags.iter().map(|tag| tag.to_uppercase()).into_renderable()`.
+/// The items render one per line, like the items of a `Vec`.
+pub trait IntoRenderable {
+    type Output: Renderable;
+
+    fn into_renderable(self) -> Self::Output;
+}
+
+impl<I> IntoRenderable for I
+where
+    I: IntoIterator + Clone,
+    I::Item: Renderable,
+{
+    type Output = Join<I, &'static str>;
+
+    fn into_renderable(self) -> Self::Output {
+        join(self, "\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Raw;
+
+    #[test]
+    fn test_join() {
+        let tags = vec!["rust", "<html>"];
+        assert_eq!(join(&tags, ", ").render(), "rust, <html>");
+        assert_eq!(
+            join(&tags, Raw("<br>")).render_escaped(Escape::Html),
+            "rust<br>&lt;html&gt;"
+        );
+        assert_eq!(join(Vec::<u8>::new(), ", ").render(), "");
+    }
+
+    #[test]
+    fn test_each_loop_info() {
+        let items = each(1..=3, |item, info| {
+            format!("{}:{}{}{} ", item, info.index, info.first, info.last)
+        });
+        assert_eq!(items.render(), "1:0truefalse 2:1falsefalse 3:2falsetrue ");
+        // Rendering again iterates again
+        assert_eq!(items.render().len(), items.render().len());
+    }
+
+    #[test]
+    fn test_into_renderable() {
+        let names = ["ada", "grace"];
+        let upper = names.iter().map(|name| name.to_uppercase());
+        assert_eq!(upper.into_renderable().render(), "ADA\nGRACE");
+    }
+}
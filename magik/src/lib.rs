@@ -1,13 +1,30 @@
 mod choosable;
-mod errors;
+mod dev_reload;
+mod escape;
+pub mod filters;
+mod iter;
 mod macros;
-mod parser;
 mod renderable;
-mod template;
+mod response;
+pub mod runtime;
+mod slots;
 
-pub use choosable::Choosable;
-pub use errors::Error;
+pub use choosable::{Choosable, ChooseWith, select};
+pub use escape::Raw;
+pub use iter::{Each, IntoRenderable, Join, LoopInfo, each, join};
 pub use macros::Children;
-pub use parser::Parser;
+pub use magik_core::{
+    Error, Escape, EscapeWriter, Location, Parser, Span, TemplateData, escape_html,
+};
+pub use magik_macro::{MagikContext, render, template, template_str};
 pub use renderable::{Renderable, TryRenderable};
-pub use template::TemplateData;
+pub use response::{Response, ResponseBody};
+pub use slots::{Slot, Slots};
+
+/// Items used by the code that `magik_macro` generates, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::dev_reload::{Field, OpaqueField, Reload, RenderableField};
+    pub use crate::renderable::{Dispatch, RenderableDispatch, TryRenderableDispatch};
+    pub use magik_core::HtmlContext;
+}
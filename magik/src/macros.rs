@@ -8,3 +8,11 @@ macro_rules! children {
         vec![$(Box::new($child)),*]
     };
 }
+
+/// Builds `Slots` from `name => content` pairs.
+#[macro_export]
+macro_rules! slots {
+    {$($name:ident => $content:expr),* $(,)?} => {
+        $crate::Slots::new()$(.with(stringify!($name), $content))*
+    };
+}
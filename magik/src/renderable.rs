@@ -1,37 +1,287 @@
-use crate::Error;
+use std::{
+    borrow::Cow,
+    fmt,
+    num::{
+        NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize, NonZeroU8,
+        NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize,
+    },
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
 
-macro_rules! impl_renderable_with_to_string {
-    ($type:ty) => {
-        impl Renderable for $type {
-            fn render(&self) -> String {
-                self.to_string()
+use crate::{Error, Escape, EscapeWriter};
+
+macro_rules! impl_renderable_with_display {
+    ($($type:ty => $hint:expr),+ $(,)?) => {
+        $(
+            impl Renderable for $type {
+                fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+                    write!(out, "{}", self)
+                }
+
+                fn size_hint(&self) -> usize {
+                    ($hint)(self)
+                }
             }
-        }
-    };
-    ($type:ty, $($rest:ty),+) => {
-        impl_renderable_with_to_string!($type);
-        impl_renderable_with_to_string!($($rest),+);
+        )+
     };
 }
 
+/// Number of characters needed to print an integer.
+fn integer_len(value: i128) -> usize {
+    let digits = unsigned_len(value.unsigned_abs());
+    if value < 0 { digits + 1 } else { digits }
+}
+
+/// Number of digits needed to print an unsigned integer.
+fn unsigned_len(value: u128) -> usize {
+    value.checked_ilog10().unwrap_or(0) as usize + 1
+}
+
 // Implementations for primitive types and common types
-impl_renderable_with_to_string!(
-    String, &String, &str, u8, u16, u32, u64, i8, i16, i32, i64, usize, isize, f32, f64, bool, char
+impl_renderable_with_display!(
+    String => |value: &String| value.len(),
+    str => |value: &str| value.len(),
+    u8 => |value: &u8| integer_len(*value as i128),
+    u16 => |value: &u16| integer_len(*value as i128),
+    u32 => |value: &u32| integer_len(*value as i128),
+    u64 => |value: &u64| integer_len(*value as i128),
+    u128 => |value: &u128| unsigned_len(*value),
+    i8 => |value: &i8| integer_len(*value as i128),
+    i16 => |value: &i16| integer_len(*value as i128),
+    i32 => |value: &i32| integer_len(*value as i128),
+    i64 => |value: &i64| integer_len(*value as i128),
+    i128 => |value: &i128| integer_len(*value),
+    usize => |value: &usize| integer_len(*value as i128),
+    isize => |value: &isize| integer_len(*value as i128),
+    f32 => |_: &f32| 8,
+    f64 => |_: &f64| 8,
+    bool => |value: &bool| if *value { 4 } else { 5 },
+    char => |value: &char| value.len_utf8(),
+    NonZeroU8 => |value: &NonZeroU8| integer_len(value.get() as i128),
+    NonZeroU16 => |value: &NonZeroU16| integer_len(value.get() as i128),
+    NonZeroU32 => |value: &NonZeroU32| integer_len(value.get() as i128),
+    NonZeroU64 => |value: &NonZeroU64| integer_len(value.get() as i128),
+    NonZeroU128 => |value: &NonZeroU128| unsigned_len(value.get()),
+    NonZeroUsize => |value: &NonZeroUsize| integer_len(value.get() as i128),
+    NonZeroI8 => |value: &NonZeroI8| integer_len(value.get() as i128),
+    NonZeroI16 => |value: &NonZeroI16| integer_len(value.get() as i128),
+    NonZeroI32 => |value: &NonZeroI32| integer_len(value.get() as i128),
+    NonZeroI64 => |value: &NonZeroI64| integer_len(value.get() as i128),
+    NonZeroI128 => |value: &NonZeroI128| integer_len(value.get()),
+    NonZeroIsize => |value: &NonZeroIsize| integer_len(value.get() as i128),
 );
 
 /// Trait for types that can be rendered to a string.
 /// This trait is used when the rendering logic is simple and does not require error handling.
 pub trait Renderable {
+    /// Writes the rendered object into `out`.
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// Writes the rendered object into `out` escaped with the given strategy.
+    /// Types whose output is already safe, like templates, override this to skip escaping.
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        match escape {
+            Escape::None => self.render_to(out),
+            // The scheme of a URL can only be checked on the whole value
+            Escape::Url => out.write_str(&escape.escape(&self.render())),
+            _ => self.render_to(&mut EscapeWriter::new(out, escape)),
+        }
+    }
+
+    /// Estimated number of bytes written by `render_to`, used to pre-size output buffers.
+    fn size_hint(&self) -> usize {
+        0
+    }
+
+    /// Media type of the rendered output, like `text/html; charset=utf-8`, when it is
+    /// known. Templates report it from their escaping or the extension of their file.
+    fn content_type(&self) -> Option<&'static str> {
+        None
+    }
+
     /// Renders the object to a string.
-    fn render(&self) -> String;
+    fn render(&self) -> String {
+        let mut output = String::with_capacity(self.size_hint());
+        // Writing into a `String` never fails
+        let _ = self.render_to(&mut output);
+        output
+    }
+
+    /// Renders the object to a string escaped with the given strategy.
+    fn render_escaped(&self, escape: Escape) -> String {
+        let mut output = String::with_capacity(self.size_hint());
+        let _ = self.render_escaped_to(&mut output, escape);
+        output
+    }
 }
 
 /// Trait for types that can be rendered with error handling.
-/// This is useful for templates that may fail to render.
-/// This trait allows for more complex rendering logic that can handle errors gracefully.
+/// This is useful for templates that may fail to render, like templates declared with
+/// `#[template(..., fallible)]` whose code blocks use `?`.
 pub trait TryRenderable {
+    /// Writes the rendered object into `out`, stopping at the first error.
+    fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error>;
+
+    /// Writes the rendered object into `out` escaped with the given strategy.
+    fn try_render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        match escape {
+            Escape::None => self.try_render_to(out),
+            Escape::Url => Ok(out.write_str(&escape.escape(&self.try_render()?))?),
+            _ => self.try_render_to(&mut EscapeWriter::new(out, escape)),
+        }
+    }
+
     /// Attempts to render the object, returning a Result.
-    fn try_render(&self) -> Result<String, Error>;
+    fn try_render(&self) -> Result<String, Error> {
+        let mut output = String::new();
+        self.try_render_to(&mut output)?;
+        Ok(output)
+    }
+}
+
+macro_rules! impl_try_renderable_with_renderable {
+    ($($type:ty),+ $(,)?) => {
+        $(
+            impl TryRenderable for $type {
+                fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error> {
+                    Ok(self.render_to(out)?)
+                }
+
+                fn try_render_escaped_to(
+                    &self,
+                    out: &mut dyn fmt::Write,
+                    escape: Escape,
+                ) -> Result<(), Error> {
+                    Ok(self.render_escaped_to(out, escape)?)
+                }
+            }
+        )+
+    };
+}
+
+impl_try_renderable_with_renderable!(
+    String,
+    str,
+    u8,
+    u16,
+    u32,
+    u64,
+    i8,
+    i16,
+    i32,
+    i64,
+    usize,
+    isize,
+    f32,
+    f64,
+    bool,
+    char,
+    (),
+);
+
+impl<T: TryRenderable + ?Sized> TryRenderable for &T {
+    fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        (**self).try_render_to(out)
+    }
+
+    fn try_render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        (**self).try_render_escaped_to(out, escape)
+    }
+}
+
+/// Writes every item of a list separated by new lines, stopping at the first error.
+fn try_render_list_to<'a, T: TryRenderable + ?Sized + 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    out: &mut dyn fmt::Write,
+    escape: Escape,
+) -> Result<(), Error> {
+    if escape == Escape::Url {
+        let mut value = String::new();
+        try_render_list_to(items, &mut value, Escape::None)?;
+        return Ok(out.write_str(&escape.escape(&value))?);
+    }
+
+    for (index, item) in items.into_iter().enumerate() {
+        if index > 0 {
+            out.write_str("\n")?;
+        }
+        item.try_render_escaped_to(out, escape)?;
+    }
+    Ok(())
+}
+
+impl<T: TryRenderable> TryRenderable for Vec<T> {
+    fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        try_render_list_to(self, out, Escape::None)
+    }
+
+    fn try_render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        try_render_list_to(self, out, escape)
+    }
+}
+
+impl<T: TryRenderable> TryRenderable for [T] {
+    fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        try_render_list_to(self, out, Escape::None)
+    }
+
+    fn try_render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        try_render_list_to(self, out, escape)
+    }
+}
+
+impl<T: TryRenderable> TryRenderable for Option<T> {
+    fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        self.try_render_escaped_to(out, Escape::None)
+    }
+
+    fn try_render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        match self {
+            Some(value) => value.try_render_escaped_to(out, escape),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Renders the value, or fails with the error turned into a `RenderError`.
+impl<T: TryRenderable, E: fmt::Display> TryRenderable for Result<T, E> {
+    fn try_render_to(&self, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        self.try_render_escaped_to(out, Escape::None)
+    }
+
+    fn try_render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        match self {
+            Ok(value) => value.try_render_escaped_to(out, escape),
+            Err(err) => Err(Error::RenderError(err.to_string().into())),
+        }
+    }
+}
+
+/// Value interpolated in a fallible template. Method resolution picks `TryRenderable`
+/// when the value implements it and `Renderable` otherwise, as in
+/// `(&Dispatch(&value)).dispatch_to(out, escape)`.
+pub struct Dispatch<'a, T: ?Sized>(pub &'a T);
+
+pub trait TryRenderableDispatch {
+    fn dispatch_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error>;
+}
+
+impl<T: TryRenderable + ?Sized> TryRenderableDispatch for Dispatch<'_, T> {
+    fn dispatch_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        self.0.try_render_escaped_to(out, escape)
+    }
+}
+
+pub trait RenderableDispatch {
+    fn dispatch_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error>;
+}
+
+impl<T: Renderable + ?Sized> RenderableDispatch for &Dispatch<'_, T> {
+    fn dispatch_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> Result<(), Error> {
+        Ok(self.0.render_escaped_to(out, escape)?)
+    }
 }
 
 impl<T: Renderable + 'static> From<T> for Box<dyn Renderable> {
@@ -40,44 +290,314 @@ impl<T: Renderable + 'static> From<T> for Box<dyn Renderable> {
     }
 }
 
+/// Sums the size hints of a list, counting the separators between items.
+fn list_size_hint<'a, T: Renderable + ?Sized + 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+) -> usize {
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| item.size_hint() + usize::from(index > 0))
+        .sum()
+}
+
+/// Writes every item of a list separated by new lines.
+fn render_list_to<'a, T: Renderable + ?Sized + 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    out: &mut dyn fmt::Write,
+    escape: Escape,
+) -> fmt::Result {
+    // Items are escaped one by one, except URLs whose scheme is checked on the whole list
+    if escape == Escape::Url {
+        let mut value = String::new();
+        render_list_to(items, &mut value, Escape::None)?;
+        return out.write_str(&escape.escape(&value));
+    }
+
+    for (index, item) in items.into_iter().enumerate() {
+        if index > 0 {
+            out.write_str("\n")?;
+        }
+        item.render_escaped_to(out, escape)?;
+    }
+    Ok(())
+}
+
+/// Implements the methods of `Renderable` by rendering the value that `self` points to.
+macro_rules! forward_renderable {
+    () => {
+        fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+            (**self).render_to(out)
+        }
+
+        fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+            (**self).render_escaped_to(out, escape)
+        }
+
+        fn size_hint(&self) -> usize {
+            (**self).size_hint()
+        }
+
+        fn content_type(&self) -> Option<&'static str> {
+            (**self).content_type()
+        }
+    };
+}
+
+/// Lets templates render values they only hold references to, like the fields bound
+/// by the variants of an enum template.
+impl<T: Renderable + ?Sized> Renderable for &T {
+    forward_renderable!();
+}
+
+// `Box<T>` is limited to sized types, since `Box<dyn Renderable>` can't be `Renderable`
+// without conflicting with `From<T> for Box<dyn Renderable>`
+impl<T: Renderable> Renderable for Box<T> {
+    forward_renderable!();
+}
+
+impl Renderable for Box<str> {
+    forward_renderable!();
+}
+
+impl<T: Renderable> Renderable for Box<[T]> {
+    forward_renderable!();
+}
+
+impl Renderable for Box<dyn Renderable + Send + Sync> {
+    forward_renderable!();
+}
+
+impl<T: Renderable + ?Sized> Renderable for Rc<T> {
+    forward_renderable!();
+}
+
+impl<T: Renderable + ?Sized> Renderable for Arc<T> {
+    forward_renderable!();
+}
+
+impl<B: Renderable + ToOwned + ?Sized> Renderable for Cow<'_, B> {
+    forward_renderable!();
+}
+
+impl Renderable for Path {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        write!(out, "{}", self.display())
+    }
+
+    fn size_hint(&self) -> usize {
+        self.as_os_str().len()
+    }
+}
+
+impl Renderable for PathBuf {
+    forward_renderable!();
+}
+
+impl<T: Renderable> Renderable for [T] {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        render_list_to(self, out, Escape::None)
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        render_list_to(self, out, escape)
+    }
+
+    fn size_hint(&self) -> usize {
+        list_size_hint(self)
+    }
+}
+
+impl<T: Renderable, const N: usize> Renderable for [T; N] {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.as_slice().render_to(out)
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        self.as_slice().render_escaped_to(out, escape)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.as_slice().size_hint()
+    }
+}
+
 impl<T: Renderable> Renderable for Vec<T> {
-    fn render(&self) -> String {
-        self.iter()
-            .map(|item| item.render())
-            .collect::<Vec<String>>()
-            .join("\n")
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.as_slice().render_to(out)
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        self.as_slice().render_escaped_to(out, escape)
+    }
+
+    fn size_hint(&self) -> usize {
+        self.as_slice().size_hint()
     }
 }
 
 impl Renderable for Vec<Box<dyn Renderable>> {
-    fn render(&self) -> String {
-        self.iter()
-            .map(|item| item.render())
-            .collect::<Vec<String>>()
-            .join("\n")
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        render_list_to(self.iter().map(|item| item.as_ref()), out, Escape::None)
     }
-}
 
-impl<T: Renderable> Renderable for &[T] {
-    fn render(&self) -> String {
-        self.iter()
-            .map(|item| item.render().clone())
-            .collect::<Vec<String>>()
-            .join("\n")
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        render_list_to(self.iter().map(|item| item.as_ref()), out, escape)
     }
+
+    fn size_hint(&self) -> usize {
+        list_size_hint(self.iter().map(|item| item.as_ref()))
+    }
+}
+
+/// Tuples render their items one after the other, without separators.
+macro_rules! impl_renderable_for_tuple {
+    ($(($($item:ident),+)),+ $(,)?) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<$($item: Renderable),+> Renderable for ($($item,)+) {
+                fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+                    let ($($item,)+) = self;
+                    $($item.render_to(out)?;)+
+                    Ok(())
+                }
+
+                fn render_escaped_to(
+                    &self,
+                    out: &mut dyn fmt::Write,
+                    escape: Escape,
+                ) -> fmt::Result {
+                    if escape == Escape::Url {
+                        return out.write_str(&escape.escape(&self.render()));
+                    }
+
+                    let ($($item,)+) = self;
+                    $($item.render_escaped_to(out, escape)?;)+
+                    Ok(())
+                }
+
+                fn size_hint(&self) -> usize {
+                    let ($($item,)+) = self;
+                    0 $(+ $item.size_hint())+
+                }
+            }
+        )+
+    };
 }
 
+impl_renderable_for_tuple!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+    (A, B, C, D, E, F, G),
+    (A, B, C, D, E, F, G, H),
+);
+
 impl<T: Renderable> Renderable for Option<T> {
-    fn render(&self) -> String {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self {
+            Some(value) => value.render_to(out),
+            None => Ok(()),
+        }
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
         match self {
-            Some(value) => value.render(),
-            None => String::new(),
+            Some(value) => value.render_escaped_to(out, escape),
+            None => Ok(()),
         }
     }
+
+    fn size_hint(&self) -> usize {
+        self.as_ref().map_or(0, Renderable::size_hint)
+    }
 }
 
 impl Renderable for () {
-    fn render(&self) -> String {
-        String::new()
+    fn render_to(&self, _out: &mut dyn fmt::Write) -> fmt::Result {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Dispatch, Renderable, RenderableDispatch, TryRenderable, TryRenderableDispatch};
+    use crate::{Children, Error, Escape, Raw, children};
+
+    #[test]
+    fn test_render_to_shared_buffer() {
+        let mut output = String::from("items: ");
+        vec![1, 2, 3]
+            .render_to(&mut output)
+            .expect("Writing should succeed");
+        assert_eq!(output, "items: 1\n2\n3");
+    }
+
+    #[test]
+    fn test_children_render_escaped_to() {
+        let children: Children = children![Raw("<hr>"), "<b>"];
+        let mut output = String::new();
+        children
+            .render_escaped_to(&mut output, Escape::Html)
+            .expect("Writing should succeed");
+        assert_eq!(output, "<hr>\n&lt;b&gt;");
+    }
+
+    #[test]
+    fn test_size_hint() {
+        assert_eq!("hello".size_hint(), 5);
+        assert_eq!((-120i32).size_hint(), 4);
+        assert_eq!(0u8.size_hint(), 1);
+        assert_eq!(vec!["ab", "cd"].size_hint(), 5);
+        assert_eq!(None::<&str>.size_hint(), 0);
+    }
+
+    #[test]
+    fn test_broader_impls() {
+        use std::{borrow::Cow, num::NonZeroU32, path::Path, rc::Rc, sync::Arc};
+
+        let shared: Box<dyn Renderable + Send + Sync> = Box::new("shared");
+        assert_eq!(shared.render(), "shared");
+        assert_eq!(Box::new(3).render(), "3");
+        assert_eq!(Rc::<str>::from("rc").render(), "rc");
+        assert_eq!(Arc::new(String::from("arc")).render(), "arc");
+        assert_eq!(Cow::Borrowed("cow").render(), "cow");
+        assert_eq!(("<b>", 1, 'c').render_escaped(Escape::Html), "&lt;b&gt;1c");
+        assert_eq!([1, 2].render(), "1\n2");
+        assert_eq!(u128::MAX.size_hint(), u128::MAX.to_string().len());
+        assert_eq!(i128::MIN.size_hint(), i128::MIN.to_string().len());
+        assert_eq!(NonZeroU32::new(42).unwrap().render(), "42");
+        assert_eq!(Path::new("a/b.txt").render(), "a/b.txt");
+
+        // Shared renderables still convert into children
+        let children: Children = vec![shared.into()];
+        assert_eq!(children.render(), "shared");
+    }
+
+    #[test]
+    fn test_try_render() {
+        let items: Vec<Result<u32, &str>> = vec![Ok(1), Ok(2)];
+        assert_eq!(items.try_render().unwrap(), "1\n2");
+        assert_eq!(Some("<b>").try_render().unwrap(), "<b>");
+
+        let failed: Vec<Result<u32, &str>> = vec![Ok(1), Err("no rows")];
+        let error = failed.try_render().unwrap_err();
+        assert!(matches!(error, Error::RenderError(message) if message == "no rows"));
+    }
+
+    #[test]
+    #[allow(clippy::needless_borrow)]
+    fn test_dispatch() {
+        let mut output = String::new();
+        (&Dispatch(&Ok::<_, &str>("<a>")))
+            .dispatch_to(&mut output, Escape::Html)
+            .unwrap();
+        (&Dispatch(&Raw("<br>")))
+            .dispatch_to(&mut output, Escape::Html)
+            .unwrap();
+        assert_eq!(output, "&lt;a&gt;<br>");
     }
 }
@@ -0,0 +1,172 @@
+use crate::{Error, Renderable};
+
+/// Content type of responses whose value doesn't report one.
+const TEXT_PLAIN: &str = "text/plain; charset=utf-8";
+
+/// Rendered value returned by the handlers of a web framework, with the content type
+/// reported by the template. The `axum`, `actix-web` and `http` features implement the
+/// response traits of the frameworks for it, answering 500 when the value fails to render.
+///
+/// ```
+/// use magik::Response;
+///
+/// let (content_type, body) = Response::new("Hello").into_parts().unwrap();
+/// assert_eq!((content_type, body.as_str()), ("text/plain; charset=utf-8", "Hello"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Response<T> {
+    value: T,
+    content_type: Option<&'static str>,
+}
+
+/// Value rendered into the body of a [`Response`], implemented for every `Renderable`
+/// and for fallible templates.
+pub trait ResponseBody {
+    fn render_body(&self) -> Result<String, Error>;
+
+    /// Media type of the body, like `text/html; charset=utf-8`, when it is known.
+    fn content_type(&self) -> Option<&'static str>;
+}
+
+impl<T: Renderable + ?Sized> ResponseBody for T {
+    fn render_body(&self) -> Result<String, Error> {
+        Ok(self.render())
+    }
+
+    fn content_type(&self) -> Option<&'static str> {
+        Renderable::content_type(self)
+    }
+}
+
+impl<T: ResponseBody> Response<T> {
+    pub fn new(value: T) -> Self {
+        Response {
+            value,
+            content_type: None,
+        }
+    }
+
+    /// Replaces the content type reported by the value. A type that isn't a valid header
+    /// value, like one holding a newline, is sent as `text/plain; charset=utf-8` instead.
+    pub fn with_content_type(mut self, content_type: &'static str) -> Self {
+        self.content_type = Some(content_type);
+        self
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        self.content_type
+            .or_else(|| self.value.content_type())
+            .filter(|content_type| is_header_value(content_type))
+            .unwrap_or(TEXT_PLAIN)
+    }
+
+    /// Renders the content type and body of the response, for frameworks without a
+    /// built-in integration, which answer the render errors with a 500 response.
+    pub fn into_parts(self) -> Result<(&'static str, String), Error> {
+        let body = self.value.render_body()?;
+        Ok((self.content_type(), body))
+    }
+
+    /// Renders the status code, content type and body of the built-in integrations,
+    /// printing render errors since the frameworks only see the 500 response.
+    #[cfg(any(feature = "http", feature = "actix-web"))]
+    fn into_status_parts(self) -> (u16, &'static str, String) {
+        match self.into_parts() {
+            Ok((content_type, body)) => (200, content_type, body),
+            Err(err) => {
+                eprintln!("magik: failed to render the response: {}", err);
+                (500, TEXT_PLAIN, "Internal Server Error".to_string())
+            }
+        }
+    }
+}
+
+/// Checks if a content type can be sent as a header value, which excludes control
+/// characters like newlines.
+fn is_header_value(value: &str) -> bool {
+    value
+        .bytes()
+        .all(|byte| byte == b'\t' || (byte >= b' ' && byte != 0x7f))
+}
+
+#[cfg(feature = "http")]
+impl<T: ResponseBody> From<Response<T>> for http::Response<String> {
+    fn from(response: Response<T>) -> Self {
+        let (status, content_type, body) = response.into_status_parts();
+
+        let mut response = http::Response::new(body);
+        *response.status_mut() =
+            http::StatusCode::from_u16(status).unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_str(content_type)
+                .unwrap_or(http::HeaderValue::from_static(TEXT_PLAIN)),
+        );
+        response
+    }
+}
+
+#[cfg(feature = "axum")]
+impl<T: ResponseBody> axum::response::IntoResponse for Response<T> {
+    fn into_response(self) -> axum::response::Response {
+        http::Response::<String>::from(self).into_response()
+    }
+}
+
+#[cfg(feature = "actix-web")]
+impl<T: ResponseBody> actix_web::Responder for Response<T> {
+    type Body = actix_web::body::BoxBody;
+
+    fn respond_to(self, _request: &actix_web::HttpRequest) -> actix_web::HttpResponse {
+        use actix_web::http::StatusCode;
+
+        let (status, content_type, body) = self.into_status_parts();
+        actix_web::HttpResponse::build(
+            StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        )
+        .content_type(content_type)
+        .body(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Failing;
+
+    impl ResponseBody for Failing {
+        fn render_body(&self) -> Result<String, Error> {
+            Err(Error::RenderError("missing".into()))
+        }
+
+        fn content_type(&self) -> Option<&'static str> {
+            Some("text/html; charset=utf-8")
+        }
+    }
+
+    #[test]
+    fn test_response_parts() {
+        let response = Response::new(42).with_content_type("application/json");
+        assert_eq!(
+            response.into_parts().unwrap(),
+            ("application/json", "42".to_string())
+        );
+        assert_eq!(
+            Response::new(Failing).into_parts().unwrap_err().to_string(),
+            Error::RenderError("missing".into()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_invalid_content_type() {
+        let response = Response::new("Hi").with_content_type("text/html\r\nX-Injected: 1");
+        assert_eq!(response.content_type(), TEXT_PLAIN);
+        assert_eq!(
+            Response::new("Hi")
+                .with_content_type("text/html; charset=utf-8")
+                .content_type(),
+            "text/html; charset=utf-8"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+use std::{borrow::Cow, cmp::Ordering};
+
+use super::Value;
+use crate::Error;
+
+/// Expression of the restricted language of runtime templates: literals, variables,
+/// field access, arithmetic, comparisons and boolean logic. There are no method calls.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Variable(String),
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Negate(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::Or => 1,
+            BinaryOp::And => 2,
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => 3,
+            BinaryOp::Add | BinaryOp::Sub => 4,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Rem => 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Op(BinaryOp),
+    Not,
+    Dot,
+    OpenParen,
+    CloseParen,
+    OpenBracket,
+    CloseBracket,
+}
+
+/// Splits an expression into tokens.
+fn tokenize(code: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = vec![];
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        let token =
+            match ch {
+                ch if ch.is_whitespace() => continue,
+                ch if ch.is_alphabetic() || ch == '_' => {
+                    let mut end = start + ch.len_utf8();
+                    while let Some((index, ch)) =
+                        chars.next_if(|(_, ch)| ch.is_alphanumeric() || *ch == '_')
+                    {
+                        end = index + ch.len_utf8();
+                    }
+                    Token::Ident(code[start..end].to_string())
+                }
+                ch if ch.is_ascii_digit() => {
+                    let mut end = start + 1;
+                    while let Some((index, _)) = chars.next_if(|(_, ch)| ch.is_ascii_digit()) {
+                        end = index + 1;
+                    }
+
+                    // A field index like `list.0` is never a float
+                    let after_dot = tokens.last() == Some(&Token::Dot);
+                    let mut lookahead = chars.clone();
+                    let is_float = !after_dot
+                        && lookahead.next().is_some_and(|(_, ch)| ch == '.')
+                        && lookahead.next().is_some_and(|(_, ch)| ch.is_ascii_digit());
+
+                    if is_float {
+                        chars.next();
+                        while let Some((index, _)) = chars.next_if(|(_, ch)| ch.is_ascii_digit()) {
+                            end = index + 1;
+                        }
+                        Token::Float(code[start..end].parse().map_err(|_| {
+                            invalid(format!("Invalid number '{}'", &code[start..end]))
+                        })?)
+                    } else {
+                        Token::Int(code[start..end].parse().map_err(|_| {
+                            invalid(format!("Invalid number '{}'", &code[start..end]))
+                        })?)
+                    }
+                }
+                '"' => {
+                    let mut text = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '"')) => break,
+                            Some((_, '\\')) => match chars.next() {
+                                Some((_, 'n')) => text.push('\n'),
+                                Some((_, 't')) => text.push('\t'),
+                                Some((_, ch)) => text.push(ch),
+                                None => return Err(invalid("Unterminated string")),
+                            },
+                            Some((_, ch)) => text.push(ch),
+                            None => return Err(invalid("Unterminated string")),
+                        }
+                    }
+                    Token::Str(text)
+                }
+                '.' => Token::Dot,
+                '(' => Token::OpenParen,
+                ')' => Token::CloseParen,
+                '[' => Token::OpenBracket,
+                ']' => Token::CloseBracket,
+                '+' => Token::Op(BinaryOp::Add),
+                '-' => Token::Op(BinaryOp::Sub),
+                '*' => Token::Op(BinaryOp::Mul),
+                '/' => Token::Op(BinaryOp::Div),
+                '%' => Token::Op(BinaryOp::Rem),
+                '!' if chars.next_if(|(_, ch)| *ch == '=').is_some() => Token::Op(BinaryOp::Ne),
+                '!' => Token::Not,
+                '=' if chars.next_if(|(_, ch)| *ch == '=').is_some() => Token::Op(BinaryOp::Eq),
+                '<' if chars.next_if(|(_, ch)| *ch == '=').is_some() => Token::Op(BinaryOp::Le),
+                '<' => Token::Op(BinaryOp::Lt),
+                '>' if chars.next_if(|(_, ch)| *ch == '=').is_some() => Token::Op(BinaryOp::Ge),
+                '>' => Token::Op(BinaryOp::Gt),
+                '&' if chars.next_if(|(_, ch)| *ch == '&').is_some() => Token::Op(BinaryOp::And),
+                '|' if chars.next_if(|(_, ch)| *ch == '|').is_some() => Token::Op(BinaryOp::Or),
+                ch => return Err(invalid(format!("Unexpected character '{}'", ch))),
+            };
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Deepest nesting of operators and parentheses accepted in an expression, which keeps
+/// parsing, evaluating and dropping it from overflowing the stack.
+const MAX_NESTING: usize = 64;
+
+/// Parses an expression, which must span the whole code.
+pub fn parse(code: &str) -> Result<Expr, Error> {
+    let tokens = tokenize(code)?;
+    let mut parser = ExprParser {
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+
+    let expr = parser.binary(0)?;
+    match parser.tokens.get(parser.pos) {
+        Some(token) => Err(invalid(format!("Unexpected {:?} in expression", token))),
+        None => Ok(expr),
+    }
+}
+
+struct ExprParser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Number of operands being parsed, one inside the other.
+    depth: usize,
+}
+
+impl ExprParser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(invalid(format!(
+                "Expected {:?}, found {:?}",
+                expected, token
+            ))),
+            None => Err(invalid(format!(
+                "Expected {:?} at the end of the expression",
+                expected
+            ))),
+        }
+    }
+
+    /// Parses operators binding tighter than `min_precedence`.
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr, Error> {
+        let mut left = self.unary()?;
+
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            if op.precedence() <= min_precedence {
+                break;
+            }
+
+            self.pos += 1;
+            let right = self.binary(op.precedence())?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+
+        Ok(left)
+    }
+
+    /// Parses an operand. Every nested operand, including those in parentheses and
+    /// indexes, is parsed through here, so this is where the nesting is limited.
+    fn unary(&mut self) -> Result<Expr, Error> {
+        if self.depth == MAX_NESTING {
+            return Err(invalid(format!(
+                "Expression nested more than {} levels deep",
+                MAX_NESTING
+            )));
+        }
+
+        self.depth += 1;
+        let expr = self.operand();
+        self.depth -= 1;
+        expr
+    }
+
+    fn operand(&mut self) -> Result<Expr, Error> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some(Token::Op(BinaryOp::Sub)) => {
+                self.pos += 1;
+                Ok(Expr::Negate(Box::new(self.unary()?)))
+            }
+            _ => self.postfix(),
+        }
+    }
+
+    fn postfix(&mut self) -> Result<Expr, Error> {
+        let mut expr = self.primary()?;
+
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    expr = match self.next() {
+                        Some(Token::Ident(field)) => {
+                            if self.peek() == Some(&Token::OpenParen) {
+                                return Err(invalid(format!(
+                                    "Method calls like '{}()' are not supported",
+                                    field
+                                )));
+                            }
+                            Expr::Field(Box::new(expr), field)
+                        }
+                        Some(Token::Int(index)) => {
+                            Expr::Index(Box::new(expr), Box::new(Expr::Literal(Value::Int(index))))
+                        }
+                        _ => return Err(invalid("Expected a field name after '.'")),
+                    };
+                }
+                Some(Token::OpenBracket) => {
+                    self.pos += 1;
+                    let index = self.binary(0)?;
+                    self.expect(Token::CloseBracket)?;
+                    expr = Expr::Index(Box::new(expr), Box::new(index));
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        match self.next() {
+            Some(Token::Int(value)) => Ok(Expr::Literal(Value::Int(value))),
+            Some(Token::Float(value)) => Ok(Expr::Literal(Value::Float(value))),
+            Some(Token::Str(value)) => Ok(Expr::Literal(Value::String(value))),
+            Some(Token::Ident(name)) => match name.as_str() {
+                "true" => Ok(Expr::Literal(Value::Bool(true))),
+                "false" => Ok(Expr::Literal(Value::Bool(false))),
+                "null" => Ok(Expr::Literal(Value::Null)),
+                _ if self.peek() == Some(&Token::OpenParen) => Err(invalid(format!(
+                    "Function calls like '{}()' are not supported",
+                    name
+                ))),
+                _ => Ok(Expr::Variable(name)),
+            },
+            Some(Token::OpenParen) => {
+                let expr = self.binary(0)?;
+                self.expect(Token::CloseParen)?;
+                Ok(expr)
+            }
+            Some(token) => Err(invalid(format!("Unexpected {:?} in expression", token))),
+            None => Err(invalid("Expected an expression")),
+        }
+    }
+}
+
+/// Scopes of variables visible while rendering, searched from the innermost.
+pub struct Scope<'v> {
+    frames: Vec<Vec<(String, Value)>>,
+    globals: &'v super::Context,
+}
+
+impl<'v> Scope<'v> {
+    pub fn new(globals: &'v super::Context) -> Self {
+        Scope {
+            frames: vec![],
+            globals,
+        }
+    }
+
+    pub fn push(&mut self) {
+        self.frames.push(vec![]);
+    }
+
+    pub fn pop(&mut self) {
+        self.frames.pop();
+    }
+
+    pub fn set(&mut self, name: &str, value: Value) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.retain(|(existing, _)| existing != name);
+            frame.push((name.to_string(), value));
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&Value> {
+        self.frames
+            .iter()
+            .rev()
+            .flat_map(|frame| frame.iter().rev())
+            .find(|(existing, _)| existing == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.globals.get(name))
+    }
+}
+
+impl Expr {
+    /// Evaluates the expression with the variables of the scope.
+    pub fn eval<'v>(&self, scope: &'v Scope) -> Result<Cow<'v, Value>, Error> {
+        match self {
+            Expr::Literal(value) => Ok(Cow::Owned(value.clone())),
+            Expr::Variable(name) => scope.get(name).map(Cow::Borrowed).ok_or_else(|| {
+                Error::RenderError(Cow::Owned(format!("Unknown variable '{}'", name)))
+            }),
+            Expr::Field(base, field) => match base.eval(scope)? {
+                Cow::Borrowed(Value::Map(entries)) => entries
+                    .get(field)
+                    .map(Cow::Borrowed)
+                    .ok_or_else(|| missing_field(field)),
+                Cow::Owned(Value::Map(mut entries)) => entries
+                    .remove(field)
+                    .map(Cow::Owned)
+                    .ok_or_else(|| missing_field(field)),
+                value => Err(render_error(format!(
+                    "Cannot read field '{}' of a {}",
+                    field,
+                    value.type_name()
+                ))),
+            },
+            Expr::Index(base, index) => {
+                let index = index.eval(scope)?;
+                match (base.eval(scope)?, index.as_ref()) {
+                    (Cow::Borrowed(Value::List(items)), Value::Int(index)) => {
+                        list_item(items, *index).map(Cow::Borrowed)
+                    }
+                    (Cow::Owned(Value::List(items)), Value::Int(index)) => {
+                        list_item(&items, *index).map(|item| Cow::Owned(item.clone()))
+                    }
+                    (Cow::Borrowed(Value::Map(entries)), Value::String(key)) => entries
+                        .get(key)
+                        .map(Cow::Borrowed)
+                        .ok_or_else(|| missing_field(key)),
+                    (Cow::Owned(Value::Map(mut entries)), Value::String(key)) => entries
+                        .remove(key)
+                        .map(Cow::Owned)
+                        .ok_or_else(|| missing_field(key)),
+                    (value, index) => Err(render_error(format!(
+                        "Cannot index a {} with a {}",
+                        value.type_name(),
+                        index.type_name()
+                    ))),
+                }
+            }
+            Expr::Not(inner) => Ok(Cow::Owned(Value::Bool(!inner.eval(scope)?.is_truthy()))),
+            Expr::Negate(inner) => match inner.eval(scope)?.as_ref() {
+                Value::Int(value) => value
+                    .checked_neg()
+                    .map(|value| Cow::Owned(Value::Int(value)))
+                    .ok_or_else(|| render_error("Integer overflow")),
+                Value::Float(value) => Ok(Cow::Owned(Value::Float(-value))),
+                value => Err(render_error(format!(
+                    "Cannot negate a {}",
+                    value.type_name()
+                ))),
+            },
+            Expr::Binary(BinaryOp::And, left, right) => {
+                let value = left.eval(scope)?.is_truthy() && right.eval(scope)?.is_truthy();
+                Ok(Cow::Owned(Value::Bool(value)))
+            }
+            Expr::Binary(BinaryOp::Or, left, right) => {
+                let value = left.eval(scope)?.is_truthy() || right.eval(scope)?.is_truthy();
+                Ok(Cow::Owned(Value::Bool(value)))
+            }
+            Expr::Binary(op, left, right) => {
+                binary(*op, left.eval(scope)?.as_ref(), right.eval(scope)?.as_ref()).map(Cow::Owned)
+            }
+        }
+    }
+}
+
+fn binary(op: BinaryOp, left: &Value, right: &Value) -> Result<Value, Error> {
+    match op {
+        BinaryOp::Eq => return Ok(Value::Bool(equals(left, right))),
+        BinaryOp::Ne => return Ok(Value::Bool(!equals(left, right))),
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+            let ordering = compare(left, right).ok_or_else(|| {
+                render_error(format!(
+                    "Cannot compare a {} with a {}",
+                    left.type_name(),
+                    right.type_name()
+                ))
+            })?;
+
+            return Ok(Value::Bool(match op {
+                BinaryOp::Lt => ordering.is_lt(),
+                BinaryOp::Le => ordering.is_le(),
+                BinaryOp::Gt => ordering.is_gt(),
+                _ => ordering.is_ge(),
+            }));
+        }
+        _ => {}
+    }
+
+    match (left, right) {
+        (Value::String(left), Value::String(right)) if op == BinaryOp::Add => {
+            Ok(Value::String(format!("{}{}", left, right)))
+        }
+        (Value::Int(left), Value::Int(right)) => {
+            let value = match op {
+                BinaryOp::Add => left.checked_add(*right),
+                BinaryOp::Sub => left.checked_sub(*right),
+                BinaryOp::Mul => left.checked_mul(*right),
+                BinaryOp::Div => left.checked_div(*right),
+                _ => left.checked_rem(*right),
+            };
+            value
+                .map(Value::Int)
+                .ok_or_else(|| render_error("Integer overflow or division by zero"))
+        }
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (left, right) = (as_float(left), as_float(right));
+            Ok(Value::Float(match op {
+                BinaryOp::Add => left + right,
+                BinaryOp::Sub => left - right,
+                BinaryOp::Mul => left * right,
+                BinaryOp::Div => left / right,
+                _ => left % right,
+            }))
+        }
+        _ => Err(render_error(format!(
+            "Cannot apply {:?} to a {} and a {}",
+            op,
+            left.type_name(),
+            right.type_name()
+        ))),
+    }
+}
+
+fn equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Int(_), Value::Float(_)) | (Value::Float(_), Value::Int(_)) => {
+            as_float(left) == as_float(right)
+        }
+        _ => left == right,
+    }
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Int(left), Value::Int(right)) => Some(left.cmp(right)),
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            as_float(left).partial_cmp(&as_float(right))
+        }
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(value) => *value as f64,
+        Value::Float(value) => *value,
+        _ => f64::NAN,
+    }
+}
+
+fn list_item(items: &[Value], index: i64) -> Result<&Value, Error> {
+    usize::try_from(index)
+        .ok()
+        .and_then(|index| items.get(index))
+        .ok_or_else(|| {
+            render_error(format!(
+                "Index {} is out of bounds for a list of {} items",
+                index,
+                items.len()
+            ))
+        })
+}
+
+fn missing_field(field: &str) -> Error {
+    render_error(format!("No field '{}'", field))
+}
+
+fn render_error(message: impl Into<Cow<'static, str>>) -> Error {
+    Error::RenderError(message.into())
+}
+
+fn invalid(message: impl Into<Cow<'static, str>>) -> Error {
+    Error::InvalidSyntax(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::Context;
+
+    fn eval(code: &str, context: &Context) -> Result<Value, Error> {
+        let scope = Scope::new(context);
+        parse(code)?.eval(&scope).map(Cow::into_owned)
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        let expr = parse("a || b && !c").unwrap();
+        assert!(matches!(expr, Expr::Binary(BinaryOp::Or, _, _)));
+
+        let context = Context::new();
+        assert_eq!(eval("1 + 2 * 3", &context).unwrap(), Value::Int(7));
+        assert_eq!(eval("(1 + 2) * 3", &context).unwrap(), Value::Int(9));
+        assert_eq!(eval("7 / 2.0", &context).unwrap(), Value::Float(3.5));
+        assert_eq!(eval("-2 < 1", &context).unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_integer_overflow() {
+        let context = Context::new().with("min", i64::MIN);
+
+        assert_eq!(eval("-(0 - 5)", &context).unwrap(), Value::Int(5));
+        assert!(eval("-min", &context).is_err());
+        assert!(eval("-(0 - 9223372036854775807 - 1)", &context).is_err());
+        assert!(eval("min - 1", &context).is_err());
+        assert!(eval("1 / 0", &context).is_err());
+    }
+
+    #[test]
+    fn test_field_access() {
+        let context = Context::new()
+            .with("user", Context::new().with("name", "Ada"))
+            .with("items", vec![10, 20]);
+
+        assert_eq!(
+            eval("user.name + \"!\"", &context).unwrap(),
+            Value::String("Ada!".into())
+        );
+        assert_eq!(eval("items.1", &context).unwrap(), Value::Int(20));
+        assert_eq!(eval("items[0] == 10", &context).unwrap(), Value::Bool(true));
+        assert!(eval("user.email", &context).is_err());
+        assert!(eval("missing", &context).is_err());
+    }
+
+    #[test]
+    fn test_method_calls_rejected() {
+        assert!(parse("name.len()").is_err());
+        assert!(parse("format(name)").is_err());
+        assert!(parse("1 +").is_err());
+        assert!(parse("\"open").is_err());
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let context = Context::new();
+        let nested = format!("{}true", "!".repeat(32));
+        assert_eq!(eval(&nested, &context).unwrap(), Value::Bool(true));
+
+        for code in [
+            format!("{}true", "!".repeat(10_000)),
+            format!("{}1", "-".repeat(10_000)),
+            format!("{}1{}", "(".repeat(10_000), ")".repeat(10_000)),
+            format!("{}0{}", "items[".repeat(10_000), "]".repeat(10_000)),
+        ] {
+            let error = parse(&code).unwrap_err();
+            assert!(error.to_string().contains("nested more than 64 levels"));
+        }
+    }
+}
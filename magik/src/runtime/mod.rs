@@ -0,0 +1,408 @@
+//! Templates compiled and rendered at runtime, for templates that aren't known when
+//! the crate is built, like templates edited by the users of an application.
+//!
+//! Runtime templates share the syntax of compiled templates, but their blocks hold a
+//! restricted expression language instead of Rust code: literals, variables, field
+//! access with `user.name` or `items.0`, arithmetic, comparisons and boolean logic,
+//! along with the `for`, `if`, `else if`, `else` and `end` tags.
+
+mod expr;
+mod value;
+
+use std::{borrow::Cow, fmt};
+
+pub use value::{Context, ToValue, Value};
+
+use crate::{Error, Escape, Location, Parser, TemplateData};
+use expr::{Expr, Scope};
+
+/// Template parsed at runtime, rendered with the variables of a `Context`.
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+    escape: Escape,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Value(Expr, Location),
+    For {
+        variable: String,
+        iterable: Expr,
+        body: Vec<Node>,
+        location: Location,
+    },
+    If {
+        branches: Vec<(Expr, Vec<Node>)>,
+        otherwise: Option<Vec<Node>>,
+        location: Location,
+    },
+}
+
+enum Tag {
+    Node(Node),
+    For(String, Expr),
+    If(Expr),
+    ElseIf(Expr),
+    Else,
+    End,
+}
+
+impl Tag {
+    fn describe(&self) -> &'static str {
+        match self {
+            Tag::ElseIf(_) => "`else if`",
+            Tag::Else => "`else`",
+            Tag::End => "`end`",
+            _ => "block",
+        }
+    }
+}
+
+impl Template {
+    /// Parses a template, reporting syntax errors with their location.
+    pub fn compile(source: &str) -> Result<Template, Error> {
+        let mut tags = vec![];
+
+        for fragment in Parser::new(source).spanned() {
+            let (data, span) = fragment?;
+            let tag = match data {
+                TemplateData::String(text) => Tag::Node(Node::Text(text.into_owned())),
+                TemplateData::Code(code) => {
+                    match classify(&code, span.location).map_err(|e| e.at(span.location))? {
+                        Some(tag) => tag,
+                        None => continue,
+                    }
+                }
+            };
+            tags.push((tag, span.location));
+        }
+
+        let mut tags = tags.into_iter();
+        let (nodes, end) = parse_body(&mut tags)?;
+        if let Some((tag, location)) = end {
+            return Err(Error::InvalidSyntax(Cow::Owned(format!(
+                "Unexpected {} without an open block",
+                tag.describe()
+            )))
+            .at(location));
+        }
+
+        Ok(Template {
+            nodes,
+            escape: Escape::None,
+        })
+    }
+
+    /// Sets how the values inserted in the template are escaped.
+    pub fn escape(mut self, escape: Escape) -> Self {
+        self.escape = escape;
+        self
+    }
+
+    pub fn render(&self, context: &Context) -> Result<String, Error> {
+        let mut out = String::new();
+        self.render_to(context, &mut out)?;
+        Ok(out)
+    }
+
+    /// Renders the template into a writer, reporting errors with their location.
+    pub fn render_to(&self, context: &Context, out: &mut dyn fmt::Write) -> Result<(), Error> {
+        let mut scope = Scope::new(context);
+        render_nodes(&self.nodes, &mut scope, self.escape, out)
+    }
+}
+
+type Tags = std::vec::IntoIter<(Tag, Location)>;
+
+/// Tag that ended a body, along with its location.
+type Closing = Option<(Tag, Location)>;
+
+/// Parses nodes until the end of the template or a tag that continues or closes the
+/// enclosing block, which is returned along with its location.
+fn parse_body(tags: &mut Tags) -> Result<(Vec<Node>, Closing), Error> {
+    let mut nodes = vec![];
+
+    while let Some((tag, location)) = tags.next() {
+        match tag {
+            Tag::Node(node) => nodes.push(node),
+            Tag::For(variable, iterable) => {
+                let (body, end) = parse_body(tags)?;
+                expect_end(end, location)?;
+                nodes.push(Node::For {
+                    variable,
+                    iterable,
+                    body,
+                    location,
+                });
+            }
+            Tag::If(condition) => {
+                let mut branches = vec![];
+                let mut condition = condition;
+                let mut otherwise = None;
+
+                loop {
+                    let (body, end) = parse_body(tags)?;
+                    branches.push((condition, body));
+
+                    match end {
+                        Some((Tag::ElseIf(next), _)) => condition = next,
+                        Some((Tag::Else, _)) => {
+                            let (body, end) = parse_body(tags)?;
+                            expect_end(end, location)?;
+                            otherwise = Some(body);
+                            break;
+                        }
+                        end => {
+                            expect_end(end, location)?;
+                            break;
+                        }
+                    }
+                }
+
+                nodes.push(Node::If {
+                    branches,
+                    otherwise,
+                    location,
+                });
+            }
+            tag => return Ok((nodes, Some((tag, location)))),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn expect_end(end: Closing, opener: Location) -> Result<(), Error> {
+    match end {
+        Some((Tag::End, _)) => Ok(()),
+        Some((tag, location)) => Err(Error::InvalidSyntax(Cow::Owned(format!(
+            "Unexpected {} in block opened at line {}",
+            tag.describe(),
+            opener.line
+        )))
+        .at(location)),
+        None => Err(
+            Error::InvalidSyntax(Cow::Borrowed("Unclosed block, expected `{{ end }}`")).at(opener),
+        ),
+    }
+}
+
+/// Recognizes the tag of a code block, returning `None` for empty blocks.
+fn classify(code: &str, location: Location) -> Result<Option<Tag>, Error> {
+    let inner = code
+        .strip_prefix('{')
+        .and_then(|code| code.strip_suffix('}'))
+        .unwrap_or(code)
+        .trim();
+
+    let tag = match inner {
+        "" => return Ok(None),
+        "end" => Tag::End,
+        "else" => Tag::Else,
+        _ => {
+            if let Some(condition) =
+                keyword_argument(inner, "else").and_then(|rest| keyword_argument(rest, "if"))
+            {
+                Tag::ElseIf(expr::parse(condition)?)
+            } else if let Some(condition) = keyword_argument(inner, "if") {
+                Tag::If(expr::parse(condition)?)
+            } else if let Some(head) = keyword_argument(inner, "for") {
+                let (variable, iterable) = head
+                    .split_once(" in ")
+                    .map(|(variable, iterable)| (variable.trim(), iterable))
+                    .filter(|(variable, _)| is_identifier(variable))
+                    .ok_or(Error::InvalidSyntax(Cow::Borrowed(
+                        "Expected a loop like `for item in items`",
+                    )))?;
+                Tag::For(variable.to_string(), expr::parse(iterable)?)
+            } else {
+                Tag::Node(Node::Value(expr::parse(inner)?, location))
+            }
+        }
+    };
+
+    Ok(Some(tag))
+}
+
+/// Returns the text after a leading keyword like `if` in `if condition`.
+fn keyword_argument<'c>(code: &'c str, keyword: &str) -> Option<&'c str> {
+    code.strip_prefix(keyword)
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|ch: char| ch.is_alphabetic() || ch == '_')
+        && name.chars().all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    scope: &mut Scope,
+    escape: Escape,
+    out: &mut dyn fmt::Write,
+) -> Result<(), Error> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.write_str(text)?,
+            Node::Value(expr, location) => {
+                let value = expr.eval(scope).map_err(|e| e.at(*location))?;
+                if let Value::Map(_) = value.as_ref() {
+                    return Err(
+                        render_error("Cannot render a map, select one of its fields").at(*location),
+                    );
+                }
+
+                out.write_str(&escape.escape(&value.to_string()))?;
+            }
+            Node::For {
+                variable,
+                iterable,
+                body,
+                location,
+            } => {
+                let items = match iterable.eval(scope).map_err(|e| e.at(*location))? {
+                    Cow::Owned(value) => value,
+                    Cow::Borrowed(value) => value.clone(),
+                };
+
+                // Entries of maps are looped as `entry.key` and `entry.value`
+                let items: Vec<Value> = match items {
+                    Value::List(items) => items,
+                    Value::Map(entries) => entries
+                        .into_iter()
+                        .map(|(key, value)| {
+                            Context::new()
+                                .with("key", key)
+                                .with("value", value)
+                                .to_value()
+                        })
+                        .collect(),
+                    Value::Null => vec![],
+                    value => {
+                        return Err(render_error(format!(
+                            "Cannot loop over a {}",
+                            value.type_name()
+                        ))
+                        .at(*location));
+                    }
+                };
+
+                for item in items {
+                    scope.push();
+                    scope.set(variable, item);
+                    let result = render_nodes(body, scope, escape, out);
+                    scope.pop();
+                    result?;
+                }
+            }
+            Node::If {
+                branches,
+                otherwise,
+                location,
+            } => {
+                let mut taken = None;
+                for (condition, body) in branches {
+                    if condition
+                        .eval(scope)
+                        .map_err(|e| e.at(*location))?
+                        .is_truthy()
+                    {
+                        taken = Some(body);
+                        break;
+                    }
+                }
+
+                if let Some(body) = taken.or(otherwise.as_ref()) {
+                    render_nodes(body, scope, escape, out)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn render_error(message: impl Into<Cow<'static, str>>) -> Error {
+    Error::RenderError(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> Context {
+        Context::new()
+            .with("name", "Ada")
+            .with("admin", true)
+            .with("orders", vec![3, 5])
+            .with("user", Context::new().with("email", "ada@example.com"))
+    }
+
+    #[test]
+    fn test_render_expressions() {
+        let template =
+            Template::compile("Hi {{ name }} ({{ user.email }}), {{ orders.0 * 2 }}").unwrap();
+        assert_eq!(
+            template.render(&context()).unwrap(),
+            "Hi Ada (ada@example.com), 6"
+        );
+    }
+
+    #[test]
+    fn test_render_control_flow() {
+        let template = Template::compile(
+            "{{ if admin && orders.1 > 4 }}admin{{ else }}user{{ end }}:\
+             {{ for order in orders }}[{{ order }}]{{ end }}",
+        )
+        .unwrap();
+        assert_eq!(template.render(&context()).unwrap(), "admin:[3][5]");
+    }
+
+    #[test]
+    fn test_render_escaped() {
+        let template = Template::compile("<b>{{ name }}</b>")
+            .unwrap()
+            .escape(Escape::Html);
+        let context = Context::new().with("name", "<Tom & Jerry>");
+        assert_eq!(
+            template.render(&context).unwrap(),
+            "<b>&lt;Tom &amp; Jerry&gt;</b>"
+        );
+    }
+
+    #[test]
+    fn test_loop_over_map() {
+        let template =
+            Template::compile("{{ for entry in user }}{{ entry.key }}={{ entry.value }}{{ end }}")
+                .unwrap();
+        assert_eq!(
+            template.render(&context()).unwrap(),
+            "email=ada@example.com"
+        );
+    }
+
+    #[test]
+    fn test_compile_errors_have_locations() {
+        let error = Template::compile("line\n  {{ if name }}open").unwrap_err();
+        assert_eq!(error.location(), Some(Location { line: 2, column: 3 }));
+
+        let error = Template::compile("{{ name.len() }}").unwrap_err();
+        assert!(matches!(error.without_location(), Error::InvalidSyntax(_)));
+        assert!(Template::compile("{{ end }}").is_err());
+
+        let nested = format!("ok\n{{{{ {}1{} }}}}", "(".repeat(1000), ")".repeat(1000));
+        let error = Template::compile(&nested).unwrap_err();
+        assert_eq!(error.location(), Some(Location { line: 2, column: 1 }));
+    }
+
+    #[test]
+    fn test_render_errors_have_locations() {
+        let template = Template::compile("ok\n{{ missing }}").unwrap();
+        let error = template.render(&context()).unwrap_err();
+        assert_eq!(error.location(), Some(Location { line: 2, column: 1 }));
+        assert!(error.to_string().contains("Unknown variable 'missing'"));
+    }
+}
@@ -0,0 +1,251 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+/// Dynamic value that runtime templates read from their context.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Value {
+    #[default]
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Name of the type of the value, used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "bool",
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Map(_) => "map",
+        }
+    }
+
+    /// Checks if the value counts as true in a condition: `null`, `false`, zero and
+    /// empty strings, lists and maps are false, anything else is true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Bool(value) => *value,
+            Value::Int(value) => *value != 0,
+            Value::Float(value) => *value != 0.0,
+            Value::String(value) => !value.is_empty(),
+            Value::List(items) => !items.is_empty(),
+            Value::Map(entries) => !entries.is_empty(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    /// Formats the value like the compiled templates render it, with list items on
+    /// separate lines and `null` as nothing.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{}", value),
+            Value::String(value) => f.write_str(value),
+            Value::List(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str("\n")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+            Value::Map(entries) => {
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str("\n")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Conversion into the `Value` tree of runtime templates, implemented for common types
+/// and derived for structs with `#[derive(MagikContext)]`.
+pub trait ToValue {
+    fn to_value(&self) -> Value;
+}
+
+impl ToValue for Value {
+    fn to_value(&self) -> Value {
+        self.clone()
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+impl<T: ToValue + ?Sized> ToValue for Box<T> {
+    fn to_value(&self) -> Value {
+        (**self).to_value()
+    }
+}
+
+macro_rules! impl_to_value {
+    ($($ty:ty => |$value:ident| $convert:expr),* $(,)?) => {
+        $(
+            impl ToValue for $ty {
+                fn to_value(&self) -> Value {
+                    let $value = self;
+                    $convert
+                }
+            }
+        )*
+    };
+}
+
+impl_to_value!(
+    bool => |value| Value::Bool(*value),
+    i8 => |value| Value::Int(i64::from(*value)),
+    i16 => |value| Value::Int(i64::from(*value)),
+    i32 => |value| Value::Int(i64::from(*value)),
+    i64 => |value| Value::Int(*value),
+    isize => |value| Value::Int(*value as i64),
+    u8 => |value| Value::Int(i64::from(*value)),
+    u16 => |value| Value::Int(i64::from(*value)),
+    u32 => |value| Value::Int(i64::from(*value)),
+    u64 => |value| i64::try_from(*value).map_or(Value::Float(*value as f64), Value::Int),
+    usize => |value| i64::try_from(*value).map_or(Value::Float(*value as f64), Value::Int),
+    f32 => |value| Value::Float(f64::from(*value)),
+    f64 => |value| Value::Float(*value),
+    char => |value| Value::String(value.to_string()),
+    str => |value| Value::String(value.to_string()),
+    String => |value| Value::String(value.clone()),
+    Cow<'_, str> => |value| Value::String(value.to_string()),
+);
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self) -> Value {
+        self.as_ref().map_or(Value::Null, ToValue::to_value)
+    }
+}
+
+impl<T: ToValue> ToValue for [T] {
+    fn to_value(&self) -> Value {
+        Value::List(self.iter().map(ToValue::to_value).collect())
+    }
+}
+
+impl<T: ToValue> ToValue for Vec<T> {
+    fn to_value(&self) -> Value {
+        self.as_slice().to_value()
+    }
+}
+
+impl<K: AsRef<str>, V: ToValue> ToValue for BTreeMap<K, V> {
+    fn to_value(&self) -> Value {
+        Value::Map(
+            self.iter()
+                .map(|(key, value)| (key.as_ref().to_string(), value.to_value()))
+                .collect(),
+        )
+    }
+}
+
+impl<K: AsRef<str>, V: ToValue, S> ToValue for HashMap<K, V, S> {
+    fn to_value(&self) -> Value {
+        Value::Map(
+            self.iter()
+                .map(|(key, value)| (key.as_ref().to_string(), value.to_value()))
+                .collect(),
+        )
+    }
+}
+
+/// Variables available to a runtime template, built by name or from a value with fields.
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+    variables: BTreeMap<String, Value>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Context::default()
+    }
+
+    /// Builds a context whose variables are the fields of the value, like the fields
+    /// of a struct deriving `MagikContext`. Values without fields give an empty context.
+    pub fn from_value(value: &impl ToValue) -> Self {
+        match value.to_value() {
+            Value::Map(variables) => Context { variables },
+            _ => Context::default(),
+        }
+    }
+
+    /// Sets a variable, replacing its previous value.
+    pub fn with(mut self, name: impl Into<String>, value: impl ToValue) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    /// Sets a variable, replacing its previous value.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl ToValue) {
+        self.variables.insert(name.into(), value.to_value());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.variables.get(name)
+    }
+}
+
+impl ToValue for Context {
+    fn to_value(&self) -> Value {
+        Value::Map(self.variables.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_value() {
+        assert_eq!(3u8.to_value(), Value::Int(3));
+        assert_eq!(u64::MAX.to_value(), Value::Float(u64::MAX as f64));
+        assert_eq!("text".to_value(), Value::String("text".into()));
+        assert_eq!(None::<i32>.to_value(), Value::Null);
+        assert_eq!(
+            vec![1, 2].to_value(),
+            Value::List(vec![Value::Int(1), Value::Int(2)])
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let list = vec!["a", "b"].to_value();
+        assert_eq!(list.to_string(), "a\nb");
+        assert_eq!(Value::Null.to_string(), "");
+        assert_eq!(Value::Float(1.5).to_string(), "1.5");
+    }
+
+    #[test]
+    fn test_context() {
+        let mut fields = BTreeMap::new();
+        fields.insert("name", "Magik");
+
+        let context = Context::from_value(&fields).with("count", 2);
+        assert_eq!(context.get("name"), Some(&Value::String("Magik".into())));
+        assert_eq!(context.get("count"), Some(&Value::Int(2)));
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt;
+
+use crate::{Escape, Renderable};
+
+/// Named pieces of content passed to a component, like the header, body and footer
+/// of a card, filled with the `slots!` macro.
+#[derive(Default)]
+pub struct Slots {
+    slots: Vec<(&'static str, Box<dyn Renderable>)>,
+}
+
+impl Slots {
+    pub fn new() -> Self {
+        Slots::default()
+    }
+
+    /// Fills a slot, replacing its previous content.
+    pub fn with(mut self, name: &'static str, content: impl Into<Box<dyn Renderable>>) -> Self {
+        self.insert(name, content);
+        self
+    }
+
+    /// Fills a slot, replacing its previous content.
+    pub fn insert(&mut self, name: &'static str, content: impl Into<Box<dyn Renderable>>) {
+        let content = content.into();
+
+        match self.slots.iter_mut().find(|(slot, _)| *slot == name) {
+            Some((_, previous)) => *previous = content,
+            None => self.slots.push((name, content)),
+        }
+    }
+
+    /// Returns the content of a slot, or `None` when it wasn't filled or its content
+    /// renders to nothing.
+    pub fn get(&self, name: &str) -> Option<&dyn Renderable> {
+        self.slots
+            .iter()
+            .find(|(slot, _)| *slot == name)
+            .map(|(_, content)| content.as_ref())
+            .filter(|content| !is_empty(*content))
+    }
+
+    /// Checks if a slot was filled with content that isn't empty.
+    pub fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Renders the content of a slot, or nothing when it wasn't filled.
+    pub fn slot(&self, name: &str) -> Slot<'_, ()> {
+        self.or(name, ())
+    }
+
+    /// Renders the content of a slot, or the fallback when it wasn't filled.
+    pub fn or<F: Renderable>(&self, name: &str, fallback: F) -> Slot<'_, F> {
+        Slot {
+            content: self.get(name),
+            fallback,
+        }
+    }
+}
+
+/// Checks if some content renders to nothing, stopping at the first character.
+fn is_empty(content: &dyn Renderable) -> bool {
+    struct Probe(bool);
+
+    impl fmt::Write for Probe {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            if s.is_empty() {
+                return Ok(());
+            }
+            self.0 = true;
+            Err(fmt::Error)
+        }
+    }
+
+    let mut probe = Probe(false);
+    let _ = content.render_to(&mut probe);
+    !probe.0
+}
+
+/// Content of a slot with the fallback rendered in its place when the slot is empty.
+pub struct Slot<'a, F> {
+    content: Option<&'a dyn Renderable>,
+    fallback: F,
+}
+
+impl<F: Renderable> Renderable for Slot<'_, F> {
+    fn render_to(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        match self.content {
+            Some(content) => content.render_to(out),
+            None => self.fallback.render_to(out),
+        }
+    }
+
+    fn render_escaped_to(&self, out: &mut dyn fmt::Write, escape: Escape) -> fmt::Result {
+        match self.content {
+            Some(content) => content.render_escaped_to(out, escape),
+            None => self.fallback.render_escaped_to(out, escape),
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        match self.content {
+            Some(content) => content.size_hint(),
+            None => self.fallback.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Escape, Raw, Renderable, slots};
+
+    #[test]
+    fn test_slots_with_fallback() {
+        let slots = slots! {
+            header => "Title",
+            body => Raw("<p>Body</p>"),
+        };
+
+        assert_eq!(slots.slot("header").render(), "Title");
+        assert_eq!(slots.or("footer", "No footer").render(), "No footer");
+        assert_eq!(slots.slot("footer").render(), "");
+        assert!(slots.has("body"));
+        assert!(!slots.has("footer"));
+    }
+
+    #[test]
+    fn test_slot_escaping() {
+        let slots = slots! { body => Raw("<p>Body</p>"), title => "<Title>" };
+
+        assert_eq!(
+            slots.slot("body").render_escaped(Escape::Html),
+            "<p>Body</p>"
+        );
+        assert_eq!(
+            slots.slot("title").render_escaped(Escape::Html),
+            "&lt;Title&gt;"
+        );
+    }
+
+    #[test]
+    fn test_empty_slot_renders_fallback() {
+        let slots = slots! { header => "", body => String::new(), footer => Raw("") };
+
+        assert_eq!(slots.or("header", "Untitled").render(), "Untitled");
+        assert_eq!(slots.or("body", "No body").render(), "No body");
+        assert!(!slots.has("header"));
+        assert!(!slots.has("footer"));
+        assert!(slots.get("body").is_none());
+    }
+
+    #[test]
+    fn test_slot_replaced() {
+        let mut slots = slots! { header => "First" };
+        slots.insert("header", "Second");
+        assert_eq!(slots.slot("header").render(), "Second");
+    }
+}
@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+use std::{error, fmt};
+
+use crate::Location;
+
+#[derive(Debug)]
+pub enum Error {
+    ParseError(Cow<'static, str>),
+    RenderError(Cow<'static, str>),
+    TemplateNotFound(Cow<'static, str>),
+    TemplateReadError(Cow<'static, str>),
+    InvalidSyntax(Cow<'static, str>),
+    /// Error raised at a known location of a template source
+    Located(Location, Box<Error>),
+}
+
+impl Error {
+    /// Attaches the template location where the error happened.
+    pub fn at(self, location: Location) -> Error {
+        match self {
+            Error::Located(_, error) => Error::Located(location, error),
+            error => Error::Located(location, Box::new(error)),
+        }
+    }
+
+    /// Returns the template location where the error happened, if known.
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Error::Located(location, _) => Some(*location),
+            _ => None,
+        }
+    }
+
+    /// Returns the error without its location.
+    pub fn without_location(&self) -> &Error {
+        match self {
+            Error::Located(_, error) => error,
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            Error::RenderError(msg) => write!(f, "Render error: {}", msg),
+            Error::TemplateNotFound(msg) => write!(f, "Template not found: {}", msg),
+            Error::TemplateReadError(msg) => write!(f, "Template read error: {}", msg),
+            Error::InvalidSyntax(msg) => write!(f, "Invalid syntax: {}", msg),
+            Error::Located(location, error) => {
+                write!(f, "{}:{}: {}", location.line, location.column, error)
+            }
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Lets render functions returning `Result<(), Error>` use `?` on writes.
+impl From<fmt::Error> for Error {
+    fn from(_: fmt::Error) -> Self {
+        Error::RenderError(Cow::Borrowed("Failed to write the output"))
+    }
+}
@@ -0,0 +1,216 @@
+use std::{borrow::Cow, fmt};
+
+/// Escaping strategy applied to interpolated values of a template.
+///
+/// Templates with HTML escaping pick the strategy of each value from its position in
+/// the markup: `Html` in text, `Attribute` in quoted attribute values, `Url` at the
+/// start of attributes like `href`, `Js` in JavaScript strings and `Css` in styles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Escape {
+    /// Values are inserted as rendered.
+    #[default]
+    None,
+    /// Characters with a special meaning in HTML are replaced by entities.
+    Html,
+    /// Like `Html`, also escaping the backticks and equal signs that could end an
+    /// attribute value in old browsers.
+    Attribute,
+    /// URLs with a scheme other than `http`, `https`, `mailto` or `tel`, like
+    /// `javascript:`, are replaced by `#`, and the rest is escaped like `Attribute`.
+    /// The value is checked as a whole, so it is rendered before being escaped.
+    Url,
+    /// Content of a JavaScript string, with quotes, backslashes, line breaks and
+    /// the characters of HTML markup written as `\uXXXX` escapes.
+    Js,
+    /// CSS value, with anything but letters, digits, spaces and `#.%-_,` written as
+    /// hexadecimal escapes.
+    Css,
+}
+
+impl Escape {
+    /// Escapes the input according to the strategy.
+    /// Returns the input unchanged when there is nothing to escape.
+    pub fn escape<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        match self {
+            Escape::None => Cow::Borrowed(input),
+            Escape::Html => escape_html(input),
+            Escape::Attribute => escape_attribute(input),
+            Escape::Url => escape_url(input),
+            Escape::Js => escape_js(input),
+            Escape::Css => escape_css(input),
+        }
+    }
+}
+
+/// Replaces `&`, `<`, `>`, `"` and `'` with their HTML entities.
+pub fn escape_html(input: &str) -> Cow<'_, str> {
+    let first = match input.find(['&', '<', '>', '"', '\'']) {
+        Some(index) => index,
+        None => return Cow::Borrowed(input),
+    };
+
+    let mut output = String::with_capacity(input.len() + 8);
+    output.push_str(&input[..first]);
+
+    for ch in input[first..].chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#x27;"),
+            _ => output.push(ch),
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+fn escape_attribute(input: &str) -> Cow<'_, str> {
+    replace_chars(
+        input,
+        |ch| matches!(ch, '&' | '<' | '>' | '"' | '\'' | '`' | '='),
+        |ch, output| match ch {
+            '`' => output.push_str("&#x60;"),
+            '=' => output.push_str("&#x3D;"),
+            ch => output.push_str(&escape_html(ch.encode_utf8(&mut [0; 4]))),
+        },
+    )
+}
+
+/// Schemes allowed at the start of interpolated URLs.
+const SAFE_SCHEMES: [&str; 4] = ["http", "https", "mailto", "tel"];
+
+fn escape_url(input: &str) -> Cow<'_, str> {
+    // Browsers ignore whitespace and control characters inside the scheme
+    let prefix: String = input
+        .chars()
+        .filter(|ch| !ch.is_ascii_whitespace() && !ch.is_ascii_control())
+        .take_while(|ch| !matches!(ch, '/' | '?' | '#'))
+        .collect();
+
+    if let Some((scheme, _)) = prefix.split_once(':')
+        && !SAFE_SCHEMES.contains(&scheme.to_ascii_lowercase().as_str())
+    {
+        return Cow::Borrowed("#");
+    }
+
+    escape_attribute(input)
+}
+
+fn escape_js(input: &str) -> Cow<'_, str> {
+    replace_chars(
+        input,
+        |ch| {
+            matches!(
+                ch,
+                '\\' | '\''
+                    | '"'
+                    | '`'
+                    | '<'
+                    | '>'
+                    | '&'
+                    | '$'
+                    | '/'
+                    | '='
+                    | '\u{2028}'
+                    | '\u{2029}'
+            ) || ch.is_control()
+        },
+        |ch, output| match ch {
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            ch => output.push_str(&format!("\\u{:04X}", ch as u32)),
+        },
+    )
+}
+
+fn escape_css(input: &str) -> Cow<'_, str> {
+    replace_chars(
+        input,
+        |ch| !(ch.is_alphanumeric() || matches!(ch, ' ' | '#' | '.' | '%' | '-' | '_' | ',')),
+        // The space ends the escape, so that a following hex digit isn't part of it
+        |ch, output| output.push_str(&format!("\\{:X} ", ch as u32)),
+    )
+}
+
+/// Copies the input, writing the characters matching `needs_escape` with `write`.
+/// Returns the input unchanged when no character matches.
+fn replace_chars(
+    input: &str,
+    needs_escape: impl Fn(char) -> bool,
+    write: impl Fn(char, &mut String),
+) -> Cow<'_, str> {
+    let first = match input.find(&needs_escape) {
+        Some(index) => index,
+        None => return Cow::Borrowed(input),
+    };
+
+    let mut output = String::with_capacity(input.len() + 8);
+    output.push_str(&input[..first]);
+
+    for ch in input[first..].chars() {
+        if needs_escape(ch) {
+            write(ch, &mut output);
+        } else {
+            output.push(ch);
+        }
+    }
+
+    Cow::Owned(output)
+}
+
+/// Writer adapter that escapes everything written through it before forwarding it.
+pub struct EscapeWriter<'a> {
+    out: &'a mut dyn fmt::Write,
+    escape: Escape,
+}
+
+impl<'a> EscapeWriter<'a> {
+    pub fn new(out: &'a mut dyn fmt::Write, escape: Escape) -> Self {
+        EscapeWriter { out, escape }
+    }
+}
+
+impl fmt::Write for EscapeWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_str(&self.escape.escape(s))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Escape, escape_html};
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html("<a href=\"x\">Tom & Jerry's</a>"),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#x27;s&lt;/a&gt;"
+        );
+        assert_eq!(escape_html("plain text"), "plain text");
+    }
+
+    #[test]
+    fn test_escape_contexts() {
+        assert_eq!(Escape::Attribute.escape("a=`b`"), "a&#x3D;&#x60;b&#x60;");
+        assert_eq!(Escape::Url.escape("/users?id=1"), "/users?id&#x3D;1");
+        assert_eq!(
+            Escape::Url.escape("HTTPS://example.com"),
+            "HTTPS://example.com"
+        );
+        assert_eq!(Escape::Url.escape(" java\tscript:alert(1)"), "#");
+        assert_eq!(Escape::Url.escape("data:text/html,x"), "#");
+        assert_eq!(
+            Escape::Js.escape("it's\n</script>"),
+            "it\\u0027s\\n\\u003C\\u002Fscript\\u003E"
+        );
+        assert_eq!(Escape::Css.escape("1px solid #000"), "1px solid #000");
+        assert_eq!(Escape::Css.escape("red;}"), "red\\3B \\7D ");
+    }
+
+    #[test]
+    fn test_escape_none() {
+        assert_eq!(Escape::None.escape("<b>"), "<b>");
+    }
+}
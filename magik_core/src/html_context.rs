@@ -0,0 +1,414 @@
+//! Lexical context of the values inserted in HTML templates. The text of a template is
+//! scanned like a browser would tokenize it, so each value is escaped for the position
+//! it lands in: text, quoted attribute value, URL, JavaScript string or CSS.
+
+use crate::Escape;
+
+/// Position reached in the markup after scanning the text before a value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlContext {
+    state: State,
+    /// Lowercase name of the current tag
+    tag: String,
+    /// Whether the current tag is a closing tag like `</p>`
+    closing: bool,
+    /// Lowercase name of the current attribute
+    attribute: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Text,
+    /// `<!-- comment -->`
+    Comment,
+    /// `<!DOCTYPE html>` and other declarations, until `>`
+    Declaration,
+    /// Right after `<` or `</`
+    TagOpen,
+    TagName,
+    /// Between the attributes of a tag
+    Tag,
+    AttributeName,
+    AfterAttributeName,
+    /// After `=`, before the value starts
+    BeforeAttributeValue,
+    AttributeValue {
+        quote: Option<char>,
+        kind: AttributeKind,
+        /// Whether only whitespace was found in the value so far
+        empty: bool,
+        script: Script,
+    },
+    /// Content of a `<script>` element
+    Script(Script),
+    /// Content of a `<style>` element
+    Style,
+}
+
+/// How the value of an attribute is interpreted by the browser.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AttributeKind {
+    Text,
+    Url,
+    /// Event handlers like `onclick`
+    Script,
+    Style,
+}
+
+/// Position in JavaScript code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Script {
+    Code,
+    /// Inside a string delimited by the quote
+    String(char),
+    /// After a backslash inside a string
+    StringEscape(char),
+    LineComment,
+    BlockComment,
+}
+
+impl Default for HtmlContext {
+    fn default() -> Self {
+        HtmlContext {
+            state: State::Text,
+            tag: String::new(),
+            closing: false,
+            attribute: String::new(),
+        }
+    }
+}
+
+impl HtmlContext {
+    /// Advances the context over static text of the template.
+    pub fn feed(&mut self, text: &str) {
+        let mut chars = text.char_indices();
+
+        while let Some((index, ch)) = chars.next() {
+            let rest = &text[index..];
+
+            self.state = match self.state {
+                State::Text => match ch {
+                    '<' => {
+                        self.closing = false;
+                        State::TagOpen
+                    }
+                    _ => State::Text,
+                },
+                State::Comment if rest.starts_with("-->") => {
+                    chars.nth(1);
+                    State::Text
+                }
+                State::Comment => State::Comment,
+                State::Declaration => match ch {
+                    '>' => State::Text,
+                    _ => State::Declaration,
+                },
+                State::TagOpen => match ch {
+                    '!' if rest.starts_with("!--") => {
+                        chars.nth(1);
+                        State::Comment
+                    }
+                    '!' | '?' => State::Declaration,
+                    '/' if !self.closing => {
+                        self.closing = true;
+                        State::TagOpen
+                    }
+                    ch if ch.is_ascii_alphabetic() => {
+                        self.tag = ch.to_ascii_lowercase().to_string();
+                        State::TagName
+                    }
+                    _ => State::Text,
+                },
+                State::TagName => match ch {
+                    '>' => self.end_tag(),
+                    '/' => State::Tag,
+                    ch if ch.is_ascii_whitespace() => State::Tag,
+                    ch => {
+                        self.tag.push(ch.to_ascii_lowercase());
+                        State::TagName
+                    }
+                },
+                State::Tag => match ch {
+                    '>' => self.end_tag(),
+                    '/' => State::Tag,
+                    ch if ch.is_ascii_whitespace() => State::Tag,
+                    ch => self.start_attribute(ch),
+                },
+                State::AttributeName => match ch {
+                    '>' => self.end_tag(),
+                    '=' => State::BeforeAttributeValue,
+                    '/' => State::Tag,
+                    ch if ch.is_ascii_whitespace() => State::AfterAttributeName,
+                    ch => {
+                        self.attribute.push(ch.to_ascii_lowercase());
+                        State::AttributeName
+                    }
+                },
+                State::AfterAttributeName => match ch {
+                    '>' => self.end_tag(),
+                    '=' => State::BeforeAttributeValue,
+                    '/' => State::Tag,
+                    ch if ch.is_ascii_whitespace() => State::AfterAttributeName,
+                    ch => self.start_attribute(ch),
+                },
+                State::BeforeAttributeValue => match ch {
+                    '>' => self.end_tag(),
+                    ch if ch.is_ascii_whitespace() => State::BeforeAttributeValue,
+                    ch => {
+                        let quote = matches!(ch, '"' | '\'').then_some(ch);
+                        let value = State::AttributeValue {
+                            quote,
+                            kind: attribute_kind(&self.attribute),
+                            empty: true,
+                            script: Script::Code,
+                        };
+                        match quote {
+                            Some(_) => value,
+                            None => self.attribute_value(value, ch, rest),
+                        }
+                    }
+                },
+                State::AttributeValue { quote, .. } => match (quote, ch) {
+                    (Some(quote), ch) if ch == quote => self.end_attribute(),
+                    (None, '>') => self.end_tag(),
+                    (None, ch) if ch.is_ascii_whitespace() => self.end_attribute(),
+                    (_, ch) => self.attribute_value(self.state, ch, rest),
+                },
+                State::Script(_) if starts_with_ignore_case(rest, "</script") => {
+                    chars.next();
+                    self.closing = true;
+                    State::TagOpen
+                }
+                State::Script(script) => {
+                    let (script, skip) = advance_script(script, ch, rest);
+                    if skip {
+                        chars.next();
+                    }
+                    State::Script(script)
+                }
+                State::Style if starts_with_ignore_case(rest, "</style") => {
+                    chars.next();
+                    self.closing = true;
+                    State::TagOpen
+                }
+                State::Style => State::Style,
+            };
+        }
+    }
+
+    /// Escaping for a value inserted at the current position, or the reason why no
+    /// escaping makes the position safe.
+    pub fn escape(&self) -> Result<Escape, String> {
+        match self.state {
+            State::Text | State::Comment | State::Declaration => Ok(Escape::Html),
+            State::TagOpen | State::TagName => {
+                Err("Values cannot be inserted in the name of a tag".to_string())
+            }
+            State::Tag | State::AttributeName | State::AfterAttributeName => Err(
+                "Values cannot be inserted between the attributes of a tag, \
+                 wrap the attribute in an `if` block instead"
+                    .to_string(),
+            ),
+            State::BeforeAttributeValue | State::AttributeValue { quote: None, .. } => {
+                Err(format!(
+                    "Quote the value of attribute `{}` to insert a value in it",
+                    self.attribute
+                ))
+            }
+            State::AttributeValue {
+                kind,
+                empty,
+                script,
+                ..
+            } => match kind {
+                AttributeKind::Text => Ok(Escape::Attribute),
+                AttributeKind::Url if empty => Ok(Escape::Url),
+                AttributeKind::Url => Ok(Escape::Attribute),
+                AttributeKind::Script => script_escape(script),
+                AttributeKind::Style => Ok(Escape::Css),
+            },
+            State::Script(script) => script_escape(script),
+            State::Style => Ok(Escape::Css),
+        }
+    }
+
+    /// Context after a block whose branches end in `self` and `other`, like an `if`
+    /// adding an attribute, or `None` when the markup that follows the block could be
+    /// read differently by each branch.
+    pub fn merge(&self, other: &HtmlContext) -> Option<HtmlContext> {
+        if self == other {
+            return Some(self.clone());
+        }
+
+        // After the name of an attribute the markup goes on as between attributes,
+        // except for an `=` that would continue the attribute of one branch only
+        let (left, right) = (
+            self.clone().between_attributes(),
+            other.clone().between_attributes(),
+        );
+        (left == right).then_some(left)
+    }
+
+    fn between_attributes(mut self) -> HtmlContext {
+        if let State::AttributeName | State::AfterAttributeName = self.state {
+            self.state = self.end_attribute();
+        }
+        self
+    }
+
+    fn start_attribute(&mut self, ch: char) -> State {
+        self.attribute = ch.to_ascii_lowercase().to_string();
+        State::AttributeName
+    }
+
+    /// Advances inside an attribute value, following the strings of event handlers.
+    fn attribute_value(&self, state: State, ch: char, rest: &str) -> State {
+        let State::AttributeValue {
+            quote,
+            kind,
+            empty,
+            script,
+        } = state
+        else {
+            return state;
+        };
+
+        let script = match kind {
+            AttributeKind::Script => advance_script(script, ch, rest).0,
+            _ => script,
+        };
+
+        State::AttributeValue {
+            quote,
+            kind,
+            empty: empty && ch.is_whitespace(),
+            script,
+        }
+    }
+
+    // Names are forgotten once they no longer matter, so that contexts reached
+    // through different markup compare equal
+    fn end_attribute(&mut self) -> State {
+        self.attribute.clear();
+        State::Tag
+    }
+
+    fn end_tag(&mut self) -> State {
+        let closing = std::mem::take(&mut self.closing);
+        let tag = std::mem::take(&mut self.tag);
+        self.attribute.clear();
+
+        match tag.as_str() {
+            "script" if !closing => State::Script(Script::Code),
+            "style" if !closing => State::Style,
+            _ => State::Text,
+        }
+    }
+}
+
+fn attribute_kind(name: &str) -> AttributeKind {
+    match name {
+        "href" | "src" | "srcset" | "action" | "formaction" | "cite" | "poster" | "background"
+        | "data" | "ping" | "manifest" | "codebase" | "longdesc" | "usemap" | "icon"
+        | "xlink:href" => AttributeKind::Url,
+        "style" => AttributeKind::Style,
+        name if name.starts_with("on") => AttributeKind::Script,
+        _ => AttributeKind::Text,
+    }
+}
+
+fn script_escape(script: Script) -> Result<Escape, String> {
+    match script {
+        Script::Code => Err(
+            "Values can only be inserted in quoted strings of JavaScript code, \
+             like `\"{{ value }}\"`"
+                .to_string(),
+        ),
+        _ => Ok(Escape::Js),
+    }
+}
+
+/// Advances JavaScript code by one character, returning whether the next character
+/// belongs to the same token, like the `/` of `*/`, and must be skipped.
+fn advance_script(script: Script, ch: char, rest: &str) -> (Script, bool) {
+    match (script, ch) {
+        (Script::Code, '"' | '\'' | '`') => (Script::String(ch), false),
+        (Script::Code, '/') if rest.starts_with("//") => (Script::LineComment, true),
+        (Script::Code, '/') if rest.starts_with("/*") => (Script::BlockComment, true),
+        (Script::String(quote), '\\') => (Script::StringEscape(quote), false),
+        (Script::String(quote), ch) if ch == quote => (Script::Code, false),
+        (Script::StringEscape(quote), _) => (Script::String(quote), false),
+        (Script::LineComment, '\n') => (Script::Code, false),
+        (Script::BlockComment, '*') if rest.starts_with("*/") => (Script::Code, true),
+        (script, _) => (script, false),
+    }
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape_after(text: &str) -> Result<Escape, String> {
+        let mut context = HtmlContext::default();
+        context.feed(text);
+        context.escape()
+    }
+
+    #[test]
+    fn test_text_and_attributes() {
+        assert_eq!(escape_after("<p class=\"a\">"), Ok(Escape::Html));
+        assert_eq!(escape_after("<!-- <a href=\""), Ok(Escape::Html));
+        assert_eq!(escape_after("<p title=\"x "), Ok(Escape::Attribute));
+        assert_eq!(escape_after("<p title='"), Ok(Escape::Attribute));
+        assert_eq!(escape_after("<A HREF=\" "), Ok(Escape::Url));
+        assert_eq!(escape_after("<a href=\"/users/"), Ok(Escape::Attribute));
+        assert_eq!(escape_after("<div style=\"color: "), Ok(Escape::Css));
+        assert_eq!(escape_after("<p title=\"a\" data-x='b'>"), Ok(Escape::Html));
+    }
+
+    #[test]
+    fn test_scripts_and_styles() {
+        assert_eq!(escape_after("<script>let a = \""), Ok(Escape::Js));
+        assert_eq!(escape_after("<script>let a = 'it\\'s "), Ok(Escape::Js));
+        assert!(escape_after("<script>let a = \"\" + ").is_err());
+        assert!(escape_after("<script>// don't\nlet a = ").is_err());
+        assert_eq!(
+            escape_after("<script>let a = \"</script>"),
+            Ok(Escape::Html)
+        );
+        assert_eq!(escape_after("<button onclick=\"go('"), Ok(Escape::Js));
+        assert!(escape_after("<button onclick=\"go(").is_err());
+        assert_eq!(escape_after("<style>p { color: "), Ok(Escape::Css));
+        assert_eq!(escape_after("<style></STYLE>"), Ok(Escape::Html));
+    }
+
+    #[test]
+    fn test_merge_branches() {
+        let context = |text: &str| {
+            let mut context = HtmlContext::default();
+            context.feed(text);
+            context
+        };
+
+        let merged = context("<input ").merge(&context("<input checked"));
+        assert_eq!(merged, Some(context("<input ")));
+        assert_eq!(context("<b>").merge(&context("<i>")), Some(context("")));
+        assert_eq!(context("<p>").merge(&context("<p class=\"")), None);
+    }
+
+    #[test]
+    fn test_unsafe_positions() {
+        assert!(escape_after("<").is_err());
+        assert!(escape_after("</").is_err());
+        assert!(escape_after("<input ").is_err());
+        assert!(escape_after("<input checked ").is_err());
+        assert!(escape_after("<a href=").is_err());
+        assert!(escape_after("<a href=/users/").is_err());
+        assert_eq!(escape_after("a < b"), Ok(Escape::Html));
+    }
+}
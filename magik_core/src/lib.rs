@@ -0,0 +1,15 @@
+//! Template parser, escaping and errors shared by `magik` and `magik-macro`.
+//! Everything here is re-exported by `magik`, the crate to depend on.
+
+mod errors;
+mod escape;
+mod html_context;
+mod parser;
+mod template;
+
+pub use errors::Error;
+pub use escape::{Escape, EscapeWriter, escape_html};
+#[doc(hidden)]
+pub use html_context::HtmlContext;
+pub use parser::Parser;
+pub use template::{Location, Span, TemplateData};
@@ -0,0 +1,574 @@
+use std::borrow::Cow;
+
+use crate::{
+    Error,
+    template::{Location, Span, TemplateData},
+};
+
+/// Tag opening a region whose contents are passed through untouched.
+const RAW_START: &str = "{% raw %}";
+/// Tag closing a region opened by [`RAW_START`].
+const RAW_END: &str = "{% endraw %}";
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Outside,
+    Inside,
+    /// Inside a comment, `{# ... #}` with the default delimiters, with the number of
+    /// comments left to close
+    Comment(usize),
+    /// Inside a `{% raw %}` region, with the location of its opening tag
+    Raw(Location),
+}
+
+pub struct Parser<'a> {
+    source: &'a str,
+    open: &'a str,
+    close: &'a str,
+    /// Markers of comments, derived from the delimiters
+    comment: (String, String),
+    chars: std::str::Chars<'a>,
+    current: Option<char>,
+    next_char: Option<char>,
+    byte_pos: usize,
+    last_byte_pos: usize,
+    location: Location,
+    fragment_start: (usize, Location),
+    state: State,
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<TemplateData<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned()
+            .map(|result| result.map(|(data, _)| data))
+    }
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        let mut chars = input.chars();
+        let current = chars.next();
+        let next_char = chars.next();
+
+        Parser {
+            source: input,
+            open: "{{",
+            close: "}}",
+            comment: comment_markers("{{", "}}"),
+            chars,
+            current,
+            next_char,
+            byte_pos: 0,
+            last_byte_pos: 0,
+            location: Location::default(),
+            fragment_start: (0, Location::default()),
+            state: State::Outside,
+        }
+    }
+
+    /// Replaces the `{{` and `}}` delimiters of code blocks. Comments are written with
+    /// the first character of `open` and the last one of `close` around `#`, like
+    /// `<# ... #>` for `<%` and `%>`.
+    ///
+    /// ```
+    /// use magik_core::Parser;
+    ///
+    /// let mut parser = Parser::new("Hello <% name %>!").with_delimiters("<%", "%>");
+    /// assert_eq!(parser.nth(1).unwrap().unwrap().as_str(), "{ name }");
+    /// ```
+    pub fn with_delimiters(mut self, open: &'a str, close: &'a str) -> Self {
+        self.open = open;
+        self.close = close;
+        self.comment = comment_markers(open, close);
+        self
+    }
+
+    /// Returns the next fragment together with its position in the source.
+    pub fn next_spanned(&mut self) -> Option<Result<(TemplateData<'a>, Span), Error>> {
+        loop {
+            match self.next_impl() {
+                Ok(Some((data, span))) => {
+                    match &data {
+                        TemplateData::String(s) if s.is_empty() => continue, // Continue loop instead of recursion
+                        TemplateData::Code(code) if code.trim().is_empty() => continue,
+                        _ => return Some(Ok((data, span))),
+                    }
+                }
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Turns the parser into an iterator of fragments together with their positions.
+    pub fn spanned(mut self) -> impl Iterator<Item = Result<(TemplateData<'a>, Span), Error>> {
+        std::iter::from_fn(move || self.next_spanned())
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        if let Some(ch) = self.current {
+            self.byte_pos += ch.len_utf8();
+            if ch == '\n' {
+                self.location.line += 1;
+                self.location.column = 1;
+            } else {
+                self.location.column += 1;
+            }
+            self.current = self.next_char;
+            self.next_char = self.chars.next();
+            Some(ch)
+        } else {
+            None
+        }
+    }
+
+    fn advance_by(&mut self, text: &str) {
+        for _ in text.chars() {
+            self.advance();
+        }
+    }
+
+    /// Source left to parse.
+    fn rest(&self) -> &'a str {
+        &self.source[self.byte_pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.current
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.next_char
+    }
+
+    /// Extracts the string between the end of the last fragment and the current position.
+    fn pending_string(&mut self) -> (TemplateData<'a>, Span) {
+        let str = &self.source[self.last_byte_pos..self.byte_pos];
+        let span = self.fragment_span();
+        self.start_fragment();
+        (TemplateData::String(Cow::Borrowed(str)), span)
+    }
+
+    /// Checks for the `-` of a `{{-` marker, which must be followed by whitespace
+    /// so that blocks starting with a negative number like `{{-1}}` are still code.
+    fn at_trim_marker(&self) -> bool {
+        self.peek() == Some('-') && self.peek_next().is_some_and(char::is_whitespace)
+    }
+
+    /// Code between `start` and `end` wrapped in braces, borrowed from the
+    /// source when it is already surrounded by the braces of its delimiters.
+    fn code_fragment(&self, start: usize, end: usize) -> Cow<'a, str> {
+        let source = self.source;
+        if source[..start].ends_with('{') && source[end..].starts_with('}') {
+            Cow::Borrowed(&source[start - 1..end + 1])
+        } else {
+            Cow::Owned(format!("{{{}}}", &source[start..end]))
+        }
+    }
+
+    /// Span from the start of the current fragment to the current position.
+    fn fragment_span(&self) -> Span {
+        let (start, location) = self.fragment_start;
+        Span {
+            range: start..self.byte_pos,
+            location,
+        }
+    }
+
+    fn start_fragment(&mut self) {
+        self.fragment_start = (self.byte_pos, self.location);
+    }
+
+    fn next_impl(&mut self) -> Result<Option<(TemplateData<'a>, Span)>, Error> {
+        loop {
+            match self.state {
+                State::Outside => {
+                    let rest = self.rest();
+
+                    if rest.is_empty() {
+                        // End of input
+                        if self.last_byte_pos < self.source.len() {
+                            let str = &self.source[self.last_byte_pos..];
+                            self.last_byte_pos = self.source.len();
+
+                            if !str.is_empty() {
+                                return Ok(Some((
+                                    TemplateData::String(Cow::Borrowed(str)),
+                                    self.fragment_span(),
+                                )));
+                            }
+                        }
+                        return Ok(None);
+                    } else if rest.starts_with("\\\\") && rest[2..].starts_with(self.open) {
+                        // \\{{ writes a backslash followed by the block
+                        self.advance(); // '\'
+                        let (str, span) = self.pending_string();
+                        self.advance(); // '\'
+
+                        self.last_byte_pos = self.byte_pos;
+                        self.start_fragment();
+
+                        return Ok(Some((str, span)));
+                    } else if rest.starts_with('\\') && rest[1..].starts_with(self.open) {
+                        // \{{ writes the opening delimiter as text, the backslash is dropped
+                        let (str, span) = self.pending_string();
+                        self.advance(); // '\'
+
+                        self.last_byte_pos = self.byte_pos;
+                        self.start_fragment();
+                        self.advance_by(self.open);
+
+                        return Ok(Some((str, span)));
+                    } else if rest.starts_with(self.open) {
+                        // Extract string before {{
+                        let (mut str, mut span) = self.pending_string();
+
+                        // Skip {{
+                        self.advance_by(self.open);
+
+                        // {{- trims the whitespace before the block
+                        if self.at_trim_marker() {
+                            self.advance(); // '-'
+                            if let TemplateData::String(Cow::Borrowed(text)) = str {
+                                let text = text.trim_end();
+                                span.range.end = span.range.start + text.len();
+                                str = TemplateData::String(Cow::Borrowed(text));
+                            }
+                        }
+
+                        self.last_byte_pos = self.byte_pos;
+                        self.state = State::Inside;
+
+                        return Ok(Some((str, span)));
+                    } else if rest.starts_with(&self.comment.0) {
+                        // Extract string before {#, the comment itself produces no data
+                        let (str, span) = self.pending_string();
+
+                        // Skip {#
+                        self.advance();
+                        self.advance();
+
+                        self.state = State::Comment(1);
+
+                        return Ok(Some((str, span)));
+                    } else if rest.starts_with(RAW_START) {
+                        // Extract string before {% raw %}
+                        let (str, span) = self.pending_string();
+                        let location = self.location;
+
+                        self.advance_by(RAW_START);
+
+                        self.last_byte_pos = self.byte_pos;
+                        self.start_fragment();
+                        self.state = State::Raw(location);
+
+                        return Ok(Some((str, span)));
+                    } else {
+                        self.advance();
+                    }
+                }
+                State::Inside => {
+                    if self.rest().starts_with(self.close) {
+                        // -}} trims the whitespace after the block
+                        let trim_after = self.byte_pos > self.last_byte_pos
+                            && self.source[..self.byte_pos].ends_with('-');
+                        let code_end = self.byte_pos - usize::from(trim_after);
+
+                        // Extract code between {{ and }} but include single braces
+                        let code = self.code_fragment(self.last_byte_pos, code_end);
+
+                        // Skip }}
+                        self.advance_by(self.close);
+
+                        let span = self.fragment_span();
+
+                        if trim_after {
+                            while self.peek().is_some_and(char::is_whitespace) {
+                                self.advance();
+                            }
+                        }
+
+                        self.last_byte_pos = self.byte_pos;
+                        self.state = State::Outside;
+                        self.start_fragment();
+
+                        return Ok(Some((TemplateData::Code(code), span)));
+                    } else if self.advance().is_none() {
+                        // End of input while in key state
+                        return Err(Error::ParseError(Cow::Borrowed(
+                            "Unexpected end of input while parsing key",
+                        ))
+                        .at(self.fragment_start.1));
+                    }
+                }
+                State::Raw(location) => {
+                    if self.rest().starts_with(RAW_END) {
+                        // The contents of the region are kept as they are
+                        let (str, span) = self.pending_string();
+
+                        self.advance_by(RAW_END);
+
+                        self.last_byte_pos = self.byte_pos;
+                        self.start_fragment();
+                        self.state = State::Outside;
+
+                        return Ok(Some((str, span)));
+                    } else if self.advance().is_none() {
+                        return Err(Error::ParseError(Cow::Borrowed(
+                            "Unexpected end of input while parsing raw block",
+                        ))
+                        .at(location));
+                    }
+                }
+                State::Comment(depth) => match self.peek() {
+                    Some(_) if self.rest().starts_with(&self.comment.0) => {
+                        self.advance();
+                        self.advance();
+                        self.state = State::Comment(depth + 1);
+                    }
+                    Some(_) if self.rest().starts_with(&self.comment.1) => {
+                        self.advance();
+                        self.advance();
+
+                        if depth == 1 {
+                            self.last_byte_pos = self.byte_pos;
+                            self.state = State::Outside;
+                            self.start_fragment();
+                        } else {
+                            self.state = State::Comment(depth - 1);
+                        }
+                    }
+                    Some(_) => {
+                        self.advance();
+                    }
+                    None => {
+                        return Err(Error::ParseError(Cow::Borrowed(
+                            "Unexpected end of input while parsing comment",
+                        ))
+                        .at(self.fragment_start.1));
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Markers opening and closing comments, `{#` and `#}` for the default delimiters.
+fn comment_markers(open: &str, close: &str) -> (String, String) {
+    let first = open.chars().next().unwrap_or('{');
+    let last = close.chars().next_back().unwrap_or('}');
+    (format!("{}#", first), format!("#{}", last))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Error, Location};
+
+    use super::{Parser, TemplateData};
+
+    // Helper macro for cleaner test assertions
+    macro_rules! assert_next {
+        ($parser:expr, $expected:expr) => {
+            let token = $parser
+                .next()
+                .expect("Expected more tokens from parser")
+                .expect("Token should parse without errors");
+            assert_eq!(token.as_str(), $expected);
+        };
+    }
+
+    #[test]
+    fn test_parser() {
+        let input = "<h1>Hello, {{ name }}!</h1> {{ test }}";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "<h1>Hello, ");
+        assert_next!(parser, "{ name }");
+        assert_next!(parser, "!</h1> ");
+        assert_next!(parser, "{ test }");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_error_handling() {
+        let input = "Hello {{ unclosed";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "Hello ");
+
+        // This should return an error
+        let result = parser
+            .next()
+            .expect("Should have next token (even if error)");
+        assert!(result.is_err());
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Unexpected end of input while parsing key")
+        );
+    }
+
+    #[test]
+    fn test_parser_collect_success() {
+        let input = "Hello {{ name }}!";
+        let parser = Parser::new(input);
+
+        let results: Result<Vec<TemplateData>, Error> = parser.collect();
+        assert!(results.is_ok());
+
+        let data = results.expect("Collection should succeed");
+        assert_eq!(data.len(), 3);
+    }
+
+    #[test]
+    fn test_parser_spans() {
+        let input = "<h1>\n  {{ name }}</h1>";
+        let mut parser = Parser::new(input);
+
+        let (_, span) = parser.next_spanned().unwrap().unwrap();
+        assert_eq!(span.range, 0..7);
+        assert_eq!(span.location, Location { line: 1, column: 1 });
+
+        let (_, span) = parser.next_spanned().unwrap().unwrap();
+        assert_eq!(&input[span.range], "{{ name }}");
+        assert_eq!(span.location, Location { line: 2, column: 3 });
+
+        let (_, span) = parser.next_spanned().unwrap().unwrap();
+        assert_eq!(&input[span.range], "</h1>");
+        assert_eq!(
+            span.location,
+            Location {
+                line: 2,
+                column: 13
+            }
+        );
+    }
+
+    #[test]
+    fn test_parser_error_location() {
+        let input = "Hello\n {{ unclosed";
+        let error = Parser::new(input)
+            .collect::<Result<Vec<TemplateData>, Error>>()
+            .expect_err("Should fail to parse");
+
+        assert_eq!(error.location(), Some(Location { line: 2, column: 2 }));
+    }
+
+    #[test]
+    fn test_parser_comments() {
+        let input = "a{# note #}b{# outer {# inner #} still comment #}c{{ x }}";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "a");
+        assert_next!(parser, "b");
+        assert_next!(parser, "c");
+        assert_next!(parser, "{ x }");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_unterminated_comment() {
+        let input = "Hello\n{# outer {# inner #}";
+        let error = Parser::new(input)
+            .collect::<Result<Vec<TemplateData>, Error>>()
+            .expect_err("Should fail to parse");
+
+        assert!(error.to_string().contains("while parsing comment"));
+        assert_eq!(error.location(), Some(Location { line: 2, column: 1 }));
+    }
+
+    #[test]
+    fn test_parser_trim_markers() {
+        let input = "<ul>\n    {{- item -}}\n</ul> {{-1}} {{ x -}} \n";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "<ul>");
+        assert_next!(parser, "{ item }");
+        assert_next!(parser, "</ul> ");
+        assert_next!(parser, "{-1}");
+        assert_next!(parser, " ");
+        assert_next!(parser, "{ x }");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_escaped_delimiter() {
+        let input = "Vue: \\{{ message }} and {{ value }}";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "Vue: ");
+        assert_next!(parser, "{{ message }} and ");
+        assert_next!(parser, "{ value }");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_backslash_before_block() {
+        let input = "C:\\\\{{ dir }}\\path";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "C:\\");
+        assert_next!(parser, "{ dir }");
+        assert_next!(parser, "\\path");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_raw_region() {
+        let input = "a{% raw %}{{ x }} {# y #}{% endraw %}{{ z }}";
+        let mut parser = Parser::new(input);
+
+        assert_next!(parser, "a");
+        assert_next!(parser, "{{ x }} {# y #}");
+        assert_next!(parser, "{ z }");
+
+        assert!(parser.next().is_none());
+
+        let error = Parser::new("{% raw %} {{")
+            .collect::<Result<Vec<TemplateData>, Error>>()
+            .expect_err("Should fail to parse");
+        assert!(error.to_string().contains("while parsing raw block"));
+    }
+
+    #[test]
+    fn test_parser_custom_delimiters() {
+        let input = "{{ literal }} <%- value %> <% x -%> !";
+        let mut parser = Parser::new(input).with_delimiters("<%", "%>");
+
+        assert_next!(parser, "{{ literal }}");
+        assert_next!(parser, "{ value }");
+        assert_next!(parser, " ");
+        assert_next!(parser, "{ x }");
+        assert_next!(parser, "!");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_comments_follow_delimiters() {
+        let input = "{{#each items}}{# kept #}<# removed <# nested #> #><% x %>";
+        let mut parser = Parser::new(input).with_delimiters("<%", "%>");
+
+        assert_next!(parser, "{{#each items}}{# kept #}");
+        assert_next!(parser, "{ x }");
+
+        assert!(parser.next().is_none());
+    }
+
+    #[test]
+    fn test_parser_collect_error() {
+        let input = "Hello {{ unclosed";
+        let parser = Parser::new(input);
+
+        let results: Result<Vec<TemplateData>, Error> = parser.collect();
+        assert!(results.is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use std::{borrow::Cow, ops::Range};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum TemplateData<'a> {
+    // Pure string data to be inserted directly into the template
+    String(Cow<'a, str>),
+    // Code blocks that will be compiled and executed
+    Code(Cow<'a, str>),
+}
+
+impl TemplateData<'_> {
+    pub fn as_str(&self) -> &str {
+        match self {
+            TemplateData::String(cow) => cow.as_ref(),
+            TemplateData::Code(cow) => cow.as_ref(),
+        }
+    }
+}
+
+/// Line and column of a position in a template source, both starting at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Location { line: 1, column: 1 }
+    }
+}
+
+/// Byte range and starting location of a fragment in a template source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub location: Location,
+}
@@ -12,13 +12,16 @@ pub struct Label<'a> {
     pub text: &'a str,
 }
 
-#[template(source = r#"
+#[template(
+    source = r#"
 {{ use crate::Button; }}
 {{ use crate::Label; }}
 <div class="form-group">
     {{ props.children }}
 </div>
-"#)]
+"#,
+    trim_blocks = true
+)]
 pub struct Form<'a> {
     pub action: &'a str,
     pub method: &'a str,
@@ -0,0 +1,32 @@
+use magik_macro::template;
+
+// Values interpolated in an html template are escaped
+#[template(source = "<p class=\"comment\">{{ props.text }}</p>", escape = "html")]
+pub struct Comment<'a> {
+    pub text: &'a str,
+}
+
+// Nested components are not escaped again, and trusted markup can be wrapped in `Raw`
+#[template(
+    source = r#"
+{{ use crate::Comment; }}
+<section>
+    {{ magik::Raw(props.banner) }}
+    {{ Comment { text: props.comment } }}
+</section>
+"#,
+    escape = "html"
+)]
+pub struct CommentSection<'a> {
+    pub banner: &'a str,
+    pub comment: &'a str,
+}
+
+fn main() {
+    let section = CommentSection {
+        banner: "<h2>Comments</h2>",
+        comment: "<script>alert('xss')</script>",
+    };
+
+    println!("{}", section);
+}
@@ -0,0 +1,26 @@
+use magik_macro::template;
+
+// The page fills only the blocks it needs, the sidebar keeps the layout's default
+#[template(
+    source = r#"{{ extends "examples/base_layout.tmp" }}
+{{ block title }}{{ props.title }} - Magik{{ end }}
+{{ block head }}<link rel="stylesheet" href="/blog.css">{{ end }}
+{{ block content }}
+        <h1>{{ props.title }}</h1>
+        <p>{{ props.body }}</p>
+    {{ end }}"#,
+    escape = "html"
+)]
+pub struct BlogPost<'a> {
+    pub title: &'a str,
+    pub body: &'a str,
+}
+
+fn main() {
+    let post = BlogPost {
+        title: "Template inheritance",
+        body: "Layouts declare blocks that pages override.",
+    };
+
+    println!("{}", post);
+}
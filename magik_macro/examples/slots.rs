@@ -0,0 +1,41 @@
+use magik::{Raw, Slots, slots};
+use magik_macro::template;
+
+#[template(
+    source = r#"<div class="card">
+    <header>{{ props.slots.or("header", "Untitled") }}</header>
+    <section>{{ props.slots.slot("body") }}</section>
+    {{ if props.slots.has("footer") }}
+    <footer>{{ props.slots.slot("footer") }}</footer>
+    {{ end }}
+</div>"#,
+    escape = "html",
+    trim_blocks = true
+)]
+pub struct Card {
+    pub slots: Slots,
+}
+
+#[template(source = "<button>{{ props.text }}</button>")]
+pub struct Button<'a> {
+    pub text: &'a str,
+}
+
+fn main() {
+    let card = Card {
+        slots: slots! {
+            header => "Profile & settings",
+            body => Raw("<p>Update your details below.</p>"),
+            footer => Button { text: "Save" },
+        },
+    };
+    println!("{}", card);
+
+    // Empty slots render their fallback, or nothing
+    let empty = Card {
+        slots: slots! {
+            body => "No content yet",
+        },
+    };
+    println!("{}", empty);
+}
@@ -0,0 +1,275 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use magik_core::Error;
+
+/// Name of the configuration file looked up at the root of the crate.
+const CONFIG_FILE: &str = "magik.toml";
+
+/// Defaults for the templates of a crate, read from a `magik.toml` file at its root
+/// or from the `[package.metadata.magik]` table of its `Cargo.toml`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    /// Directories searched in order for template files, relative to the crate root
+    pub dirs: Vec<String>,
+    /// Extension added to template paths that don't have one
+    pub extension: Option<String>,
+    pub escape: Option<magik_core::Escape>,
+    pub context: Option<String>,
+    /// Names of the crate's own filters, see `desugar_filters`
+    pub filters: Vec<String>,
+    /// File the configuration was read from, tracked as an input of the crate
+    pub file: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads the configuration of the crate at `root`, `magik.toml` taking precedence
+    /// over the Cargo metadata. A crate without either uses the defaults.
+    pub fn load(root: &Path) -> Result<Config, Error> {
+        let config_file = root.join(CONFIG_FILE);
+        if config_file.exists() {
+            let table = read_table(&config_file)?;
+            let config =
+                Config::from_table(&table).map_err(|err| invalid_config(&config_file, err))?;
+            return Ok(config.read_from(config_file));
+        }
+
+        let manifest = root.join("Cargo.toml");
+        if !manifest.exists() {
+            return Ok(Config::default());
+        }
+
+        let table = read_table(&manifest)?;
+        match table
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("magik"))
+        {
+            Some(toml::Value::Table(table)) => Config::from_table(table)
+                .map(|config| config.read_from(manifest.clone()))
+                .map_err(|err| invalid_config(&manifest, err)),
+            Some(_) => Err(invalid_config(
+                &manifest,
+                "'package.metadata.magik' must be a table",
+            )),
+            // Still tracked, so that adding the table rebuilds the crate
+            None => Ok(Config::default().read_from(manifest)),
+        }
+    }
+
+    fn read_from(self, file: PathBuf) -> Config {
+        Config {
+            file: Some(file),
+            ..self
+        }
+    }
+
+    fn from_table(table: &toml::Table) -> Result<Config, String> {
+        let mut config = Config::default();
+
+        for (key, value) in table {
+            match key.as_str() {
+                "dirs" => {
+                    let dirs = value
+                        .as_array()
+                        .ok_or("'dirs' must be an array of strings")?;
+                    for dir in dirs {
+                        let dir = dir.as_str().ok_or("'dirs' must be an array of strings")?;
+                        config.dirs.push(dir.to_string());
+                    }
+                }
+                "extension" => {
+                    let extension = value.as_str().ok_or("'extension' must be a string")?;
+                    config.extension = Some(extension.trim_start_matches('.').to_string());
+                }
+                "escape" => {
+                    let escape = value.as_str().ok_or("'escape' must be a string")?;
+                    config.escape =
+                        Some(parse_escape(escape).ok_or("Expected 'html' or 'none' escape mode")?);
+                }
+                "context" => {
+                    let context = value.as_str().ok_or("'context' must be a string")?;
+                    config.context = Some(context.to_string());
+                }
+                "filters" => {
+                    let filters = value
+                        .as_array()
+                        .ok_or("'filters' must be an array of names")?;
+                    for filter in filters {
+                        let filter = filter
+                            .as_str()
+                            .filter(|filter| syn::parse_str::<syn::Ident>(filter).is_ok())
+                            .ok_or("'filters' must be an array of names")?;
+                        config.filters.push(filter.to_string());
+                    }
+                }
+                _ => return Err(format!("Unknown option '{}'", key)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Lists the files a template path may refer to, in the order they are searched.
+    /// The crate root is searched after the configured directories.
+    pub fn candidates(&self, root: &Path, path: &str) -> Vec<PathBuf> {
+        let mut names = vec![PathBuf::from(path)];
+        if let Some(extension) = &self.extension
+            && Path::new(path).extension().is_none()
+        {
+            names.push(PathBuf::from(format!("{}.{}", path, extension)));
+        }
+
+        let mut dirs: Vec<PathBuf> = self.dirs.iter().map(|dir| root.join(dir)).collect();
+        if !dirs.iter().any(|dir| dir == root) {
+            dirs.push(root.to_path_buf());
+        }
+
+        dirs.iter()
+            .flat_map(|dir| names.iter().map(|name| dir.join(name)))
+            .collect()
+    }
+
+    /// Searches the template directories for the first file matching the path.
+    pub fn find(&self, root: &Path, path: &str) -> Result<PathBuf, Error> {
+        let candidates = self.candidates(root, path);
+
+        if let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) {
+            return Ok(found.clone());
+        }
+
+        let tried: String = candidates
+            .iter()
+            .map(|candidate| format!("\n  - {}", candidate.display()))
+            .collect();
+
+        Err(Error::TemplateNotFound(Cow::Owned(format!(
+            "Template '{}' was not found, tried:{}",
+            path, tried
+        ))))
+    }
+}
+
+pub fn parse_escape(value: &str) -> Option<magik_core::Escape> {
+    match value {
+        "html" => Some(magik_core::Escape::Html),
+        "none" => Some(magik_core::Escape::None),
+        _ => None,
+    }
+}
+
+fn read_table(path: &Path) -> Result<toml::Table, Error> {
+    let text = std::fs::read_to_string(path).map_err(|_| {
+        Error::TemplateReadError(Cow::Owned(format!("Failed to read {}", path.display())))
+    })?;
+
+    text.parse::<toml::Table>()
+        .map_err(|err| invalid_config(path, err.message()))
+}
+
+fn invalid_config(path: &Path, message: impl std::fmt::Display) -> Error {
+    Error::TemplateReadError(Cow::Owned(format!(
+        "Invalid magik configuration in {}: {}",
+        path.display(),
+        message
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(text: &str) -> Result<Config, String> {
+        Config::from_table(&text.parse::<toml::Table>().unwrap())
+    }
+
+    #[test]
+    fn test_config_options() {
+        let config = config(
+            r#"
+            dirs = ["templates", "shared/templates"]
+            extension = ".html"
+            escape = "html"
+            context = "page"
+            filters = ["currency"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.dirs, ["templates", "shared/templates"]);
+        assert_eq!(config.extension.as_deref(), Some("html"));
+        assert_eq!(config.escape, Some(magik_core::Escape::Html));
+        assert_eq!(config.context.as_deref(), Some("page"));
+        assert_eq!(config.filters, ["currency"]);
+    }
+
+    #[test]
+    fn test_config_errors() {
+        assert!(config("dirs = \"templates\"").is_err());
+        assert!(config("escape = \"xml\"").is_err());
+        assert!(config("unknown = 1").is_err());
+        assert!(config("filters = [\"two words\"]").is_err());
+    }
+
+    #[test]
+    fn test_candidates() {
+        let config = Config {
+            dirs: vec!["templates".into(), "shared".into()],
+            extension: Some("tmp".into()),
+            ..Config::default()
+        };
+
+        let root = Path::new("/crate");
+        assert_eq!(
+            config.candidates(root, "user"),
+            [
+                root.join("templates/user"),
+                root.join("templates/user.tmp"),
+                root.join("shared/user"),
+                root.join("shared/user.tmp"),
+                root.join("user"),
+                root.join("user.tmp"),
+            ]
+        );
+        assert_eq!(
+            Config::default().candidates(root, "user.tmp"),
+            [root.join("user.tmp")]
+        );
+    }
+
+    #[test]
+    fn test_find_reports_tried_locations() {
+        let config = Config {
+            dirs: vec!["templates".into(), "shared".into()],
+            ..Config::default()
+        };
+
+        let message = config
+            .find(Path::new("/nonexistent"), "missing.tmp")
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("/nonexistent/templates/missing.tmp"));
+        assert!(message.contains("/nonexistent/shared/missing.tmp"));
+        assert!(message.contains("/nonexistent/missing.tmp"));
+    }
+
+    #[test]
+    fn test_load_metadata() {
+        let root = std::env::temp_dir().join(format!("magik-config-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[package.metadata.magik]\ndirs = [\"templates\"]\n",
+        )
+        .unwrap();
+
+        let config = Config::load(&root);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let config = config.unwrap();
+        assert_eq!(config.dirs, ["templates"]);
+        assert_eq!(config.file, Some(root.join("Cargo.toml")));
+    }
+}
@@ -0,0 +1,428 @@
+use magik_core::{Span, TemplateData};
+use proc_macro2::TokenStream;
+
+use crate::source::TemplateSource;
+
+/// Template fragment together with its position in the source.
+pub type Fragment<'a> = (TemplateData<'a>, Span);
+
+/// Tree of a template where control flow tags like `{{ for x in list }}` ... `{{ end }}`
+/// hold the fragments between them. Tags keep their `opener` fragment to report errors.
+#[derive(Clone)]
+pub enum Node<'t, 'a> {
+    /// Text or code block
+    Fragment(&'t Fragment<'a>),
+    /// `for` or `while` loop, with its head like `for item in list`
+    Loop {
+        head: TokenStream,
+        body: Vec<Node<'t, 'a>>,
+        opener: &'t Fragment<'a>,
+    },
+    /// `if` with its `else if` branches, each head like `if condition`
+    If {
+        branches: Vec<(TokenStream, Vec<Node<'t, 'a>>)>,
+        otherwise: Option<Vec<Node<'t, 'a>>>,
+        opener: &'t Fragment<'a>,
+    },
+    /// `match` with its head like `match value` and its arms like `Some(x) =>`
+    Match {
+        head: TokenStream,
+        arms: Vec<(TokenStream, Vec<Node<'t, 'a>>)>,
+        opener: &'t Fragment<'a>,
+    },
+    /// Named block like `{{ block title }}` that templates extending this one can override,
+    /// with the source its body comes from
+    Block {
+        name: String,
+        body: Vec<Node<'t, 'a>>,
+        source: &'t TemplateSource,
+        opener: &'t Fragment<'a>,
+    },
+    /// `{{ extends "base.tmp" }}` tag, resolved before the template is compiled
+    Extends {
+        path: String,
+        opener: &'t Fragment<'a>,
+    },
+    /// `{{ include "header.tmp" }}` tag, with the nodes of the included template and
+    /// its source once they are spliced in
+    Include {
+        path: String,
+        body: Vec<Node<'t, 'a>>,
+        source: Option<&'t TemplateSource>,
+        opener: &'t Fragment<'a>,
+    },
+}
+
+impl<'t, 'a> Node<'t, 'a> {
+    /// Fragment where the node starts.
+    pub fn opener(&self) -> &'t Fragment<'a> {
+        match self {
+            Node::Fragment(fragment) => fragment,
+            Node::Loop { opener, .. }
+            | Node::If { opener, .. }
+            | Node::Match { opener, .. }
+            | Node::Block { opener, .. }
+            | Node::Extends { opener, .. }
+            | Node::Include { opener, .. } => opener,
+        }
+    }
+
+    /// Visits the text and code fragments of the node and of the nodes inside it.
+    pub fn for_each_fragment(&self, visit: &mut impl FnMut(&'t Fragment<'a>)) {
+        let children: Vec<&Vec<Node<'t, 'a>>> = match self {
+            Node::Fragment(fragment) => {
+                visit(fragment);
+                return;
+            }
+            Node::Extends { .. } => return,
+            Node::Loop { body, .. } | Node::Block { body, .. } | Node::Include { body, .. } => {
+                vec![body]
+            }
+            Node::If {
+                branches,
+                otherwise,
+                ..
+            } => branches
+                .iter()
+                .map(|(_, body)| body)
+                .chain(otherwise.as_ref())
+                .collect(),
+            Node::Match { arms, .. } => arms.iter().map(|(_, body)| body).collect(),
+        };
+
+        for node in children.into_iter().flatten() {
+            node.for_each_fragment(visit);
+        }
+    }
+
+    /// Checks if the node is text made only of whitespace.
+    pub fn is_whitespace(&self) -> bool {
+        matches!(
+            self,
+            Node::Fragment((TemplateData::String(text), _)) if text.trim().is_empty()
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum BlockKind {
+    Loop,
+    If,
+    Match,
+    Named,
+}
+
+enum Tag {
+    Open(BlockKind, TokenStream),
+    ElseIf(TokenStream),
+    Else,
+    Arm(TokenStream),
+    End,
+    Extends(String),
+    Include(String),
+}
+
+impl Tag {
+    fn describe(&self) -> &'static str {
+        match self {
+            Tag::Extends(_) => "`extends`",
+            Tag::Include(_) => "`include`",
+            Tag::Open(..) => "block",
+            Tag::ElseIf(_) => "`else if`",
+            Tag::Else => "`else`",
+            Tag::Arm(_) => "match arm",
+            Tag::End => "`end`",
+        }
+    }
+}
+
+/// Checks if a code block is a control flow tag rather than a self-contained block.
+pub fn is_control_tag(code: &str) -> bool {
+    classify(code).is_some()
+}
+
+/// Builds the tree of a template, reporting tags that are unclosed or out of place.
+pub fn parse_nodes<'t, 'a>(
+    tmp: &'t [Fragment<'a>],
+    source: &'t TemplateSource,
+) -> syn::Result<Vec<Node<'t, 'a>>> {
+    let mut fragments = tmp.iter();
+    let (nodes, end) = parse_body(&mut fragments, source)?;
+
+    match end {
+        Some((tag, (_, span))) => Err(source.error(
+            span,
+            format!("Unexpected {} without an open block", tag.describe()),
+        )),
+        None => Ok(nodes),
+    }
+}
+
+type Fragments<'t, 'a> = std::slice::Iter<'t, Fragment<'a>>;
+
+/// Tag that ended a body, along with its fragment.
+type Closing<'t, 'a> = Option<(Tag, &'t Fragment<'a>)>;
+
+/// Parses nodes until the end of the template or a tag that continues or closes
+/// the enclosing block, which is returned along with its fragment.
+fn parse_body<'t, 'a>(
+    fragments: &mut Fragments<'t, 'a>,
+    source: &'t TemplateSource,
+) -> syn::Result<(Vec<Node<'t, 'a>>, Closing<'t, 'a>)> {
+    let mut nodes = vec![];
+
+    while let Some(fragment) = fragments.next() {
+        let tag = match &fragment.0 {
+            TemplateData::Code(code) => classify(code),
+            TemplateData::String(_) => None,
+        };
+
+        match tag {
+            None => nodes.push(Node::Fragment(fragment)),
+            Some(Tag::Open(kind, head)) => {
+                nodes.push(parse_block(kind, head, fragment, fragments, source)?)
+            }
+            Some(Tag::Extends(path)) => nodes.push(Node::Extends {
+                path,
+                opener: fragment,
+            }),
+            Some(Tag::Include(path)) => nodes.push(Node::Include {
+                path,
+                body: vec![],
+                source: None,
+                opener: fragment,
+            }),
+            Some(tag) => return Ok((nodes, Some((tag, fragment)))),
+        }
+    }
+
+    Ok((nodes, None))
+}
+
+fn parse_block<'t, 'a>(
+    kind: BlockKind,
+    head: TokenStream,
+    opener: &'t Fragment<'a>,
+    fragments: &mut Fragments<'t, 'a>,
+    source: &'t TemplateSource,
+) -> syn::Result<Node<'t, 'a>> {
+    match kind {
+        BlockKind::Loop => {
+            let (body, end) = parse_body(fragments, source)?;
+            expect_end(end, opener, source)?;
+            Ok(Node::Loop { head, body, opener })
+        }
+        BlockKind::Named => {
+            let (body, end) = parse_body(fragments, source)?;
+            expect_end(end, opener, source)?;
+            Ok(Node::Block {
+                name: head.to_string(),
+                body,
+                source,
+                opener,
+            })
+        }
+        BlockKind::If => {
+            let mut branches = vec![];
+            let mut head = head;
+
+            loop {
+                let (body, end) = parse_body(fragments, source)?;
+                branches.push((head, body));
+
+                match end {
+                    Some((Tag::ElseIf(next), _)) => head = next,
+                    Some((Tag::Else, _)) => {
+                        let (body, end) = parse_body(fragments, source)?;
+                        expect_end(end, opener, source)?;
+                        return Ok(Node::If {
+                            branches,
+                            otherwise: Some(body),
+                            opener,
+                        });
+                    }
+                    end => {
+                        expect_end(end, opener, source)?;
+                        return Ok(Node::If {
+                            branches,
+                            otherwise: None,
+                            opener,
+                        });
+                    }
+                }
+            }
+        }
+        BlockKind::Match => {
+            let (body, mut end) = parse_body(fragments, source)?;
+
+            // Only whitespace may appear between the match and its first arm
+            if let Some(node) = body.iter().find(|node| !node.is_whitespace()) {
+                return Err(source.error(
+                    &node.opener().1,
+                    "Expected a match arm like `{{ Some(x) => }}`",
+                ));
+            }
+
+            let mut arms = vec![];
+            while let Some((Tag::Arm(pattern), _)) = end {
+                let (body, next) = parse_body(fragments, source)?;
+                arms.push((pattern, body));
+                end = next;
+            }
+
+            expect_end(end, opener, source)?;
+            Ok(Node::Match { head, arms, opener })
+        }
+    }
+}
+
+fn expect_end(end: Closing, opener: &Fragment, source: &TemplateSource) -> syn::Result<()> {
+    match end {
+        Some((Tag::End, _)) => Ok(()),
+        Some((tag, (_, span))) => Err(source.error(
+            span,
+            format!(
+                "Unexpected {} in block opened at line {}",
+                tag.describe(),
+                opener.1.location.line
+            ),
+        )),
+        None => Err(source.error(&opener.1, "Unclosed block, expected `{{ end }}`")),
+    }
+}
+
+/// Recognizes control flow tags, returning `None` for any other code block.
+fn classify(code: &str) -> Option<Tag> {
+    let inner = code.strip_prefix('{')?.strip_suffix('}')?.trim();
+
+    if inner == "end" {
+        return Some(Tag::End);
+    }
+
+    if inner == "else" {
+        return Some(Tag::Else);
+    }
+
+    if let Some(condition) = inner.strip_prefix("else")
+        && condition.starts_with(char::is_whitespace)
+        && first_word(condition.trim_start()) == Some("if")
+    {
+        let head = condition.trim_start();
+        return parses_as::<syn::ExprIf>(&format!("{} {{}}", head))
+            .then(|| head.parse().ok())
+            .flatten()
+            .map(Tag::ElseIf);
+    }
+
+    if let Some(name) = keyword_argument(inner, "block") {
+        return syn::parse_str::<syn::Ident>(name)
+            .ok()
+            .map(|name| Tag::Open(BlockKind::Named, quote::quote! { #name }));
+    }
+
+    if let Some(path) = keyword_argument(inner, "extends") {
+        return syn::parse_str::<syn::LitStr>(path)
+            .ok()
+            .map(|path| Tag::Extends(path.value()));
+    }
+
+    if let Some(path) = keyword_argument(inner, "include") {
+        return syn::parse_str::<syn::LitStr>(path)
+            .ok()
+            .map(|path| Tag::Include(path.value()));
+    }
+
+    if inner.ends_with("=>") {
+        return parses_as::<syn::ExprMatch>(&format!("match () {{ {} {{}} }}", inner))
+            .then(|| inner.parse().ok())
+            .flatten()
+            .map(Tag::Arm);
+    }
+
+    // Complete expressions like `{{ if a { b } else { c } }}` are regular code blocks
+    if syn::parse_str::<syn::Block>(code).is_ok() {
+        return None;
+    }
+
+    let block = format!("{} {{}}", inner);
+    let kind = match first_word(inner)? {
+        "for" if parses_as::<syn::ExprForLoop>(&block) => BlockKind::Loop,
+        "while" if parses_as::<syn::ExprWhile>(&block) => BlockKind::Loop,
+        "if" if parses_as::<syn::ExprIf>(&block) => BlockKind::If,
+        "match" if parses_as::<syn::ExprMatch>(&block) => BlockKind::Match,
+        _ => return None,
+    };
+
+    Some(Tag::Open(kind, inner.parse().ok()?))
+}
+
+/// Returns the text after a leading keyword like `block` in `block title`.
+fn keyword_argument<'c>(code: &'c str, keyword: &str) -> Option<&'c str> {
+    code.strip_prefix(keyword)
+        .filter(|rest| rest.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+fn first_word(text: &str) -> Option<&str> {
+    text.split(|ch: char| !ch.is_alphanumeric() && ch != '_')
+        .next()
+        .filter(|word| !word.is_empty())
+}
+
+fn parses_as<T: syn::parse::Parse>(code: &str) -> bool {
+    syn::parse_str::<T>(code).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert!(matches!(classify("{ end }"), Some(Tag::End)));
+        assert!(matches!(classify("{ else }"), Some(Tag::Else)));
+        assert!(matches!(
+            classify("{ else if x > 1 }"),
+            Some(Tag::ElseIf(_))
+        ));
+        assert!(matches!(
+            classify("{ Some(x) if x > 1 => }"),
+            Some(Tag::Arm(_))
+        ));
+        assert!(matches!(
+            classify("{ for item in props.items }"),
+            Some(Tag::Open(BlockKind::Loop, _))
+        ));
+        assert!(matches!(
+            classify("{ while let Some(x) = stack.pop() }"),
+            Some(Tag::Open(BlockKind::Loop, _))
+        ));
+        assert!(matches!(
+            classify("{ if let Some(name) = props.name }"),
+            Some(Tag::Open(BlockKind::If, _))
+        ));
+        assert!(matches!(
+            classify("{ match props.status }"),
+            Some(Tag::Open(BlockKind::Match, _))
+        ));
+        assert!(matches!(
+            classify("{ block title }"),
+            Some(Tag::Open(BlockKind::Named, _))
+        ));
+        assert!(matches!(
+            classify("{ extends \"base.tmp\" }"),
+            Some(Tag::Extends(path)) if path == "base.tmp"
+        ));
+        assert!(matches!(
+            classify("{ include \"header.tmp\" }"),
+            Some(Tag::Include(path)) if path == "header.tmp"
+        ));
+    }
+
+    #[test]
+    fn test_classify_regular_code() {
+        assert!(classify("{ if a { 1 } else { 2 } }").is_none());
+        assert!(classify("{ props.ending }").is_none());
+        assert!(classify("{ format_items() }").is_none());
+    }
+}
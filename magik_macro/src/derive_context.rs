@@ -0,0 +1,57 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index};
+
+/// Implements `magik::runtime::ToValue` for a struct, turning its named fields into
+/// the entries of a map and the fields of a tuple struct into a list.
+pub fn derive_to_value(input: &DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "MagikContext can only be derived for structs",
+        ));
+    };
+
+    let value = match &data.fields {
+        Fields::Named(fields) => {
+            let entries = fields.named.iter().map(|field| {
+                let ident = &field.ident;
+                let name = ident.as_ref().map(ToString::to_string);
+                quote! {
+                    entries.insert(
+                        String::from(#name),
+                        magik::runtime::ToValue::to_value(&self.#ident),
+                    );
+                }
+            });
+
+            quote! {
+                let mut entries = std::collections::BTreeMap::new();
+                #(#entries)*
+                magik::runtime::Value::Map(entries)
+            }
+        }
+        Fields::Unnamed(fields) => {
+            let items = (0..fields.unnamed.len()).map(Index::from);
+            quote! {
+                magik::runtime::Value::List(vec![
+                    #(magik::runtime::ToValue::to_value(&self.#items)),*
+                ])
+            }
+        }
+        Fields::Unit => quote! {
+            magik::runtime::Value::Map(std::collections::BTreeMap::new())
+        },
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics magik::runtime::ToValue for #name #ty_generics #where_clause {
+            fn to_value(&self) -> magik::runtime::Value {
+                #value
+            }
+        }
+    })
+}
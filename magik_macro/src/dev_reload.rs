@@ -0,0 +1,59 @@
+use std::path::Path;
+
+use quote::quote;
+
+use crate::utils::escape_tokens;
+
+/// Generates the start of `render_to` under the `dev-reload` feature, which renders the
+/// current contents of the template file in debug builds, looking up the `props.field`
+/// blocks in a table of the struct's fields. When the file can't be interpreted this way
+/// the code after it renders the compiled template instead.
+pub fn compile_reload(
+    fields: &syn::Fields,
+    file: &Path,
+    delimiters: Option<&(String, String)>,
+    context: &str,
+    escape: magik_core::Escape,
+    trim_blocks: bool,
+    fallible: bool,
+) -> proc_macro2::TokenStream {
+    let path = file.to_string_lossy();
+    let (open, close) = delimiters
+        .map(|(open, close)| (open.as_str(), close.as_str()))
+        .unwrap_or(("{{", "}}"));
+    let escape = escape_tokens(escape);
+
+    let result = if fallible {
+        quote! { Ok(result?) }
+    } else {
+        quote! { result }
+    };
+
+    let fields = fields.iter().filter_map(|field| field.ident.as_ref());
+    let names = fields.clone().map(|ident| ident.to_string());
+
+    quote! {
+        #[cfg(debug_assertions)]
+        {
+            use magik::__private::{OpaqueField as _, RenderableField as _};
+
+            let magik__reload = magik::__private::Reload {
+                path: #path,
+                delimiters: (#open, #close),
+                context: #context,
+                escape: #escape,
+                trim_blocks: #trim_blocks,
+            };
+            let magik__lookup = |name: &str| -> Option<&dyn magik::Renderable> {
+                match name {
+                    #(#names => (&magik::__private::Field(&self.#fields)).as_renderable(),)*
+                    _ => None,
+                }
+            };
+
+            if let Some(result) = magik__reload.render(&magik__lookup, magik__out) {
+                return #result;
+            }
+        }
+    }
+}
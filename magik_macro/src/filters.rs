@@ -0,0 +1,149 @@
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::quote;
+
+/// Rewrites a value block with filters, like `{ props.name | trim | truncate(20) }`,
+/// into method calls on the value, `(props.name).trim().truncate(20)`, with the
+/// filter traits of `magik::filters` in scope. Returns `None` for blocks without
+/// filters, left to be compiled as they are.
+///
+/// A `|` separates filters only outside of parentheses and brackets, and only when
+/// every part after it names a built-in filter or one of the `custom` filters, with
+/// optional arguments. Bitwise ors like `a | b` and closures keep their meaning.
+pub fn desugar_filters(code: &str, custom: &[String]) -> Option<String> {
+    let stream: TokenStream = code.parse().ok()?;
+    let mut trees = stream.into_iter();
+    let inner = match (trees.next(), trees.next()) {
+        (Some(TokenTree::Group(group)), None) if group.delimiter() == Delimiter::Brace => {
+            group.stream()
+        }
+        _ => return None,
+    };
+
+    let mut segments: Vec<Vec<TokenTree>> = vec![vec![]];
+    let mut after_joint_pipe = false;
+
+    for tree in inner {
+        if let TokenTree::Punct(punct) = &tree {
+            // Statements are never filtered
+            if punct.as_char() == ';' {
+                return None;
+            }
+
+            let is_pipe =
+                punct.as_char() == '|' && punct.spacing() == Spacing::Alone && !after_joint_pipe;
+            after_joint_pipe = punct.as_char() == '|' && punct.spacing() == Spacing::Joint;
+
+            if is_pipe {
+                segments.push(vec![]);
+                continue;
+            }
+        } else {
+            after_joint_pipe = false;
+        }
+
+        if let Some(segment) = segments.last_mut() {
+            segment.push(tree);
+        }
+    }
+
+    let (value, filters) = segments.split_first()?;
+    if filters.is_empty() || value.is_empty() {
+        return None;
+    }
+
+    let calls = filters
+        .iter()
+        .map(|filter| filter_call(filter, custom))
+        .collect::<Option<Vec<_>>>()?;
+    let value: TokenStream = value.iter().cloned().collect();
+
+    Some(
+        quote! {{
+            use magik::filters::{
+                DateFilters as _, DefaultFilter as _, JsonFilter as _, NumberFilters as _,
+                TextFilters as _,
+            };
+            (#value) #(.#calls)*
+        }}
+        .to_string(),
+    )
+}
+
+/// Parses a filter like `upper` or `truncate(20)` into the method call it stands for.
+fn filter_call(tokens: &[TokenTree], custom: &[String]) -> Option<TokenStream> {
+    match tokens {
+        [TokenTree::Ident(name)] if is_filter(name, custom) => Some(quote! { #name() }),
+        [TokenTree::Ident(name), TokenTree::Group(arguments)]
+            if is_filter(name, custom) && arguments.delimiter() == Delimiter::Parenthesis =>
+        {
+            Some(quote! { #name #arguments })
+        }
+        _ => None,
+    }
+}
+
+fn is_filter(name: &proc_macro2::Ident, custom: &[String]) -> bool {
+    let name = name.to_string();
+    BUILTIN_FILTERS.contains(&name.as_str()) || custom.contains(&name)
+}
+
+/// Methods of the filter traits of `magik::filters`.
+const BUILTIN_FILTERS: &[&str] = &[
+    "upper",
+    "lower",
+    "capitalize",
+    "title",
+    "trim",
+    "truncate",
+    "indent",
+    "wordwrap",
+    "urlencode",
+    "escape",
+    "number",
+    "pluralize",
+    "date",
+    "default",
+    "json",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls(code: &str) -> Option<String> {
+        // Keeps the calls after the imports, without whitespace
+        desugar_filters(code, &["currency".to_string()]).map(|code| {
+            let code: String = code.split_whitespace().collect();
+            code[code.find("};").unwrap() + 2..code.len() - 1].to_string()
+        })
+    }
+
+    #[test]
+    fn test_desugar_filters() {
+        assert_eq!(
+            calls("{ props.name | upper | truncate(20) }").as_deref(),
+            Some("(props.name).upper().truncate(20)")
+        );
+        assert_eq!(
+            calls("{ a || b | default(\"none\") }").as_deref(),
+            Some("(a||b).default(\"none\")")
+        );
+        assert_eq!(
+            calls("{ props.total | currency }").as_deref(),
+            Some("(props.total).currency()")
+        );
+    }
+
+    #[test]
+    fn test_plain_rust_is_kept() {
+        assert_eq!(calls("{ props.name }"), None);
+        assert_eq!(calls("{ FLAGS | MASK }"), None);
+        assert_eq!(calls("{ a | b }"), None);
+        assert_eq!(calls("{ a | mask(1) }"), None);
+        assert_eq!(calls("{ (a | upper) }"), None);
+        assert_eq!(calls("{ a | b.c }"), None);
+        assert_eq!(calls("{ items.iter().map(|item| item.len()) }"), None);
+        assert_eq!(calls("{ let x = a | b; }"), None);
+        assert_eq!(calls("{ |x| x }"), None);
+    }
+}
@@ -0,0 +1,127 @@
+use magik_core::{Span, TemplateData};
+
+use crate::{control_flow::Node, is_block_returning_value, source::TemplateSource};
+
+/// Finds the `{{ extends "base.tmp" }}` tag of a template, returning the path of the base
+/// template and the span of the tag. The tag must come before any other content.
+pub fn extends_of(nodes: &[Node]) -> Option<(String, Span)> {
+    match nodes.iter().find(|node| !node.is_whitespace()) {
+        Some(Node::Extends { path, opener }) => Some((path.clone(), opener.1.clone())),
+        _ => None,
+    }
+}
+
+/// Resolves a chain of templates, from the template of the struct to the base template
+/// at its root, into a single tree: the blocks of each base are replaced by the blocks
+/// that the templates extending it override.
+pub fn resolve<'t, 'a>(
+    mut chain: Vec<(Vec<Node<'t, 'a>>, &'t TemplateSource)>,
+) -> syn::Result<Vec<Node<'t, 'a>>> {
+    let Some((mut tree, _)) = chain.pop() else {
+        return Ok(vec![]);
+    };
+
+    for (nodes, source) in chain.into_iter().rev() {
+        let mut statements = vec![];
+        let mut overridden: Vec<String> = vec![];
+
+        // The first node is the `extends` tag of the template
+        for node in nodes
+            .into_iter()
+            .filter(|node| !node.is_whitespace())
+            .skip(1)
+        {
+            match node {
+                Node::Block {
+                    ref name, opener, ..
+                } => {
+                    if overridden.contains(name) {
+                        return Err(source.error(
+                            &opener.1,
+                            format!("Block '{}' is overridden more than once", name),
+                        ));
+                    }
+                    overridden.push(name.clone());
+
+                    if !override_block(&mut tree, &node) {
+                        return Err(source.error(
+                            &opener.1,
+                            format!("Block '{}' is not declared in the base template", name),
+                        ));
+                    }
+                }
+                Node::Fragment(fragment) if is_statement(&fragment.0) => statements.push(node),
+                node => {
+                    return Err(source.error(
+                        &node.opener().1,
+                        "Only blocks and statements are allowed in a template that extends another",
+                    ));
+                }
+            }
+        }
+
+        // Statements like `use` declarations apply to the whole template
+        statements.append(&mut tree);
+        tree = statements;
+    }
+
+    Ok(tree)
+}
+
+/// Replaces the body of every block in the tree named like the overriding block,
+/// returning whether any was found.
+fn override_block<'t, 'a>(nodes: &mut [Node<'t, 'a>], block: &Node<'t, 'a>) -> bool {
+    let Node::Block {
+        name: new_name,
+        body: new_body,
+        source: new_source,
+        ..
+    } = block
+    else {
+        return false;
+    };
+
+    let mut found = false;
+
+    for node in nodes {
+        let children: Vec<&mut Vec<Node<'t, 'a>>> = match node {
+            Node::Block {
+                name, body, source, ..
+            } if name == new_name => {
+                *body = new_body.clone();
+                *source = new_source;
+                found = true;
+                continue;
+            }
+            Node::Fragment(_) | Node::Extends { .. } => continue,
+            Node::Loop { body, .. } | Node::Block { body, .. } | Node::Include { body, .. } => {
+                vec![body]
+            }
+            Node::If {
+                branches,
+                otherwise,
+                ..
+            } => branches
+                .iter_mut()
+                .map(|(_, body)| body)
+                .chain(otherwise.as_mut())
+                .collect(),
+            Node::Match { arms, .. } => arms.iter_mut().map(|(_, body)| body).collect(),
+        };
+
+        for body in children {
+            found |= override_block(body, block);
+        }
+    }
+
+    found
+}
+
+/// Checks if a fragment is a code block that doesn't render a value, like `{{ use crate::Button; }}`.
+fn is_statement(data: &TemplateData) -> bool {
+    match data {
+        TemplateData::Code(code) => syn::parse_str::<syn::Block>(code)
+            .is_ok_and(|block| !block.stmts.is_empty() && !is_block_returning_value(&block)),
+        TemplateData::String(_) => false,
+    }
+}
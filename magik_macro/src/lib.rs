@@ -1,101 +1,618 @@
 extern crate proc_macro;
 
 mod check_return;
+mod config;
+mod control_flow;
+mod derive_context;
+mod dev_reload;
+mod filters;
+mod inheritance;
+mod size_hint;
+mod source;
+mod template_set;
 mod utils;
 
 use check_return::*;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{ItemStruct, parse::Parse, parse_macro_input};
+use std::path::{Path, PathBuf};
 
-use crate::utils::{compile_template, parse_template, read_template_file};
+use syn::{DeriveInput, ItemEnum, ItemStruct, parse::Parse, parse_macro_input};
+
+use crate::{
+    config::{Config, parse_escape},
+    control_flow::parse_nodes,
+    dev_reload::compile_reload,
+    inheritance::resolve,
+    size_hint::compile_size_hint,
+    source::TemplateSource,
+    template_set::TemplateSet,
+    utils::{
+        RelativeTo, TemplateLoader, compile_template, crate_root, parse_template, trim_blocks,
+    },
+};
 
 #[proc_macro_attribute]
 pub fn template(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(attr as Attributes);
 
-    let source = if let Some(path) = &input.path {
-        read_template_file(path).map_err(|e| syn::Error::new_spanned(path, e.to_string()))
-    } else if let Some(source) = &input.source {
-        Ok(source.clone())
-    } else {
-        Err(syn::Error::new(
-            proc_macro2::Span::call_site(),
-            "Either 'path' or 'source' attribute must be provided",
-        ))
-    };
-
-    let source = match source {
-        Ok(src) => src,
-        Err(err) => return err.to_compile_error().into(),
-    };
+    match parse_macro_input!(item as syn::Item) {
+        syn::Item::Struct(item) => expand_template(input, item),
+        syn::Item::Enum(item) => expand_enum_template(input, item),
+        item => syn::Error::new_spanned(item, "#[template] can only be used on structs and enums")
+            .to_compile_error()
+            .into(),
+    }
+}
 
+/// Inline template, the shorthand of `#[template(source = "...")]` taking the same options.
+#[proc_macro_attribute]
+pub fn template_str(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(attr as Attributes);
     let item = parse_macro_input!(item as ItemStruct);
 
-    let template = match parse_template(source.as_str()) {
-        Ok(template) => template,
+    if let Some(path) = &input.path {
+        return syn::Error::new_spanned(
+            path,
+            "'template_str' takes an inline template, use '#[template(path = ...)]' for files",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    expand_template(input, item)
+}
+
+/// Renders an inline template into a `String`, without declaring a struct.
+///
+/// Values are bound to names with `name = value`, and the template can also read
+/// any variable in scope where the macro is called. The `escape` option works as
+/// in `template_str`, defaulting to the `escape` of the crate configuration:
+///
+/// ```ignore
+/// use magik::render;
+///
+/// let page = render!("<h1>{{ title }}</h1>", escape = "html", title = "Hello");
+/// ```
+#[proc_macro]
+pub fn render(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as RenderInput);
+    let source = TemplateSource::inline(&input.source);
+
+    let config = match crate_root().and_then(|root| Config::load(&root)) {
+        Ok(config) => config,
         Err(err) => {
-            return syn::Error::new_spanned(item, err.to_string())
+            return syn::Error::new(proc_macro2::Span::call_site(), err.to_string())
                 .to_compile_error()
                 .into();
         }
     };
+    let escape = input
+        .escape
+        .unwrap_or_else(|| config.escape.unwrap_or_default());
+    let files = config.file.iter().map(|file| file.to_string_lossy());
+
+    let bindings = input.bindings.iter().map(|(name, value)| {
+        quote! { let #name = #value; }
+    });
+    let code = parse_template(&source, None)
+        .and_then(|template| {
+            let nodes = parse_nodes(&template, &source)?;
+            compile_template(
+                &nodes,
+                &source,
+                quote! { #(#bindings)* },
+                escape,
+                &config.filters,
+                false,
+            )
+        })
+        .unwrap_or_else(|err| err.to_compile_error());
+
+    quote! {
+        {
+            #(const _: &[u8] = include_bytes!(#files);)*
+            let mut magik__buffer = String::new();
+            let magik__render = |magik__out: &mut dyn std::fmt::Write| -> std::fmt::Result {
+                #code
+            };
+            magik__render(&mut magik__buffer)
+                .expect("a Renderable implementation returned an error");
+            magik__buffer
+        }
+    }
+    .into()
+}
+
+/// Makes a struct usable as the context of runtime templates, with its fields as variables.
+#[proc_macro_derive(MagikContext)]
+pub fn derive_magik_context(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    derive_context::derive_to_value(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn expand_template(input: Attributes, item: ItemStruct) -> TokenStream {
+    expand_template_inner(input, item)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+fn expand_template_inner(
+    input: Attributes,
+    item: ItemStruct,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let call_site = proc_macro2::Span::call_site();
+    let to_error = |err: magik_core::Error| syn::Error::new(call_site, err.to_string());
+
+    let root = crate_root().map_err(to_error)?;
+    let config = Config::load(&root).map_err(to_error)?;
+
+    let compiled = compile_attributes(&input, &config, root, Some(&item.fields))?;
+    let implement = if input.fallible {
+        implement_try_renderable
+    } else {
+        implement_renderable
+    };
 
-    let code = compile_template(&template, &item, input.context.as_deref());
+    Ok(implement(
+        &quote! { #item },
+        &item.ident,
+        &item.generics,
+        &compiled,
+    ))
+}
 
-    implement_renderable(&item, &code)
+/// Expands an enum whose variants each declare their template with `#[template(...)]`,
+/// taking the options of the enum's own attribute as defaults. The fields of the
+/// variant are bound by name, and tuple fields as `_0`, `_1`, and so on.
+fn expand_enum_template(input: Attributes, mut item: ItemEnum) -> TokenStream {
+    expand_enum_template_inner(input, &mut item)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
 }
 
-fn implement_renderable(item: &ItemStruct, code: &proc_macro2::TokenStream) -> TokenStream {
-    let name = &item.ident;
-    let generics = &item.generics;
+fn expand_enum_template_inner(
+    input: Attributes,
+    item: &mut ItemEnum,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let call_site = proc_macro2::Span::call_site();
+    let to_error = |err: magik_core::Error| syn::Error::new(call_site, err.to_string());
+
+    if let Some(lit) = input.path.as_ref().or(input.source.as_ref()) {
+        return Err(syn::Error::new_spanned(
+            lit,
+            "Set the template of each variant with its own #[template(...)] attribute",
+        ));
+    }
+
+    let root = crate_root().map_err(to_error)?;
+    let config = Config::load(&root).map_err(to_error)?;
+
+    let mut arms = vec![];
+    let mut hint_arms = vec![];
+    let mut content_type_arms = vec![];
+    let mut tracking = vec![];
+
+    for variant in &mut item.variants {
+        let position = variant
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("template"))
+            .ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &variant.ident,
+                    format!(
+                        "Variant `{}` has no #[template(...)] attribute",
+                        variant.ident
+                    ),
+                )
+            })?;
+        let attr = variant.attrs.remove(position);
+        let variant_input = attr.parse_args::<Attributes>()?;
+
+        if variant_input.fallible {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`fallible` applies to the whole enum, set it in the enum's #[template(...)]",
+            ));
+        }
+
+        let variant_input = variant_input.or(&input);
+        let compiled = compile_attributes(&variant_input, &config, root.clone(), None)?;
+
+        let ident = &variant.ident;
+        let pattern = match &variant.fields {
+            syn::Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote! { Self::#ident { #(#names),* } }
+            }
+            syn::Fields::Unnamed(fields) => {
+                let names = (0..fields.unnamed.len())
+                    .map(|index| syn::Ident::new(&format!("_{}", index), call_site));
+                quote! { Self::#ident(#(#names),*) }
+            }
+            syn::Fields::Unit => quote! { Self::#ident },
+        };
+
+        let code = &compiled.code;
+        let size_hint = &compiled.size_hint;
+        let content_type = &compiled.content_type;
+        arms.push(quote! {
+            #[allow(unused_variables)]
+            #pattern => { #code }
+        });
+        hint_arms.push(quote! {
+            Self::#ident { .. } => { #size_hint }
+        });
+        content_type_arms.push(quote! {
+            Self::#ident { .. } => #content_type,
+        });
+        tracking.push(compiled.tracking);
+    }
+
+    let implement = if input.fallible {
+        implement_try_renderable
+    } else {
+        implement_renderable
+    };
+
+    let compiled = CompiledTemplate {
+        code: quote! { match self { #(#arms)* } },
+        size_hint: quote! { match self { #(#hint_arms)* } },
+        content_type: quote! { match self { #(#content_type_arms)* } },
+        tracking: quote! { #(#tracking)* },
+    };
+
+    Ok(implement(
+        &quote! { #item },
+        &item.ident,
+        &item.generics,
+        &compiled,
+    ))
+}
+
+/// Template of a struct or of an enum variant, compiled into the body of a render function.
+struct CompiledTemplate {
+    code: proc_macro2::TokenStream,
+    size_hint: proc_macro2::TokenStream,
+    /// `Option<&'static str>` expression with the media type of the output
+    content_type: proc_macro2::TokenStream,
+    /// Constants that make Cargo rebuild the crate when a template file changes
+    tracking: proc_macro2::TokenStream,
+}
+
+/// Loads and compiles the template of the attributes. The fields of structs allow
+/// reloading file templates under the `dev-reload` feature.
+fn compile_attributes(
+    input: &Attributes,
+    config: &Config,
+    root: PathBuf,
+    fields: Option<&syn::Fields>,
+) -> syn::Result<CompiledTemplate> {
+    let call_site = proc_macro2::Span::call_site();
+
+    let lit = input
+        .path
+        .as_ref()
+        .or(input.source.as_ref())
+        .ok_or_else(|| {
+            syn::Error::new(
+                call_site,
+                "Either 'path' or 'source' attribute must be provided",
+            )
+        })?;
+    let mut loader = TemplateLoader::new(
+        config,
+        root,
+        input.relative_to.unwrap_or_default(),
+        lit.clone(),
+    );
+
+    let (source, file) = match &input.path {
+        Some(path) => {
+            let (source, file) = loader
+                .load(&path.value())
+                .map_err(|e| syn::Error::new_spanned(path, e.to_string()))?;
+            (source, Some(file))
+        }
+        None => (TemplateSource::inline(lit), None),
+    };
+
+    let reload_file = file.clone().filter(|_| cfg!(feature = "dev-reload"));
+    let set = TemplateSet::load(source, file, &mut loader, input.delimiters.as_ref())?;
+    let sources = &set.sources;
+
+    let mut templates = sources
+        .iter()
+        .map(|source| parse_template(source, input.delimiters.as_ref()))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    if input.trim_blocks {
+        templates
+            .iter_mut()
+            .for_each(|template| trim_blocks(template));
+    }
+
+    let trees = templates
+        .iter()
+        .zip(sources)
+        .map(|(template, source)| parse_nodes(template, source))
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let chain = set
+        .chain()
+        .into_iter()
+        .map(|index| (set.expand_includes(index, &trees), &sources[index]))
+        .collect();
+    let nodes = resolve(chain)?;
+
+    let source = &sources[0];
+    let escape = input
+        .escape
+        .or(config.escape)
+        .unwrap_or_else(|| match &source.path {
+            Some(path) if path.ends_with(".html") || path.ends_with(".htm") => {
+                magik_core::Escape::Html
+            }
+            _ => magik_core::Escape::None,
+        });
+
+    let context = input.context.clone().or(config.context.clone());
+    let context_ident = syn::Ident::new(context.as_deref().unwrap_or("props"), call_site);
+    let filters: Vec<String> = (config.filters.iter().cloned())
+        .chain(input.filters.iter().map(|filter| filter.to_string()))
+        .collect();
+    let code = compile_template(
+        &nodes,
+        source,
+        quote! { let #context_ident = self; },
+        escape,
+        &filters,
+        input.fallible,
+    )?;
+    let size_hint = compile_size_hint(&nodes, context.as_deref(), input.fallible);
+    let reload = reload_file.zip(fields).map(|(file, fields)| {
+        compile_reload(
+            fields,
+            &file,
+            input.delimiters.as_ref(),
+            context.as_deref().unwrap_or("props"),
+            escape,
+            input.trim_blocks,
+            input.fallible,
+        )
+    });
+
+    let content_type = match content_type(escape, source.path.as_deref()) {
+        Some(content_type) => quote! { Some(#content_type) },
+        None => quote! { None },
+    };
+
+    Ok(CompiledTemplate {
+        code: quote! { #reload #code },
+        size_hint,
+        content_type,
+        tracking: loader.tracking(),
+    })
+}
+
+/// Media type of the output of a template, known from HTML escaping or the extension
+/// of its file.
+fn content_type(escape: magik_core::Escape, path: Option<&str>) -> Option<&'static str> {
+    let extension = path
+        .and_then(|path| Path::new(path).extension())
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        _ if escape == magik_core::Escape::Html => Some("text/html; charset=utf-8"),
+        Some("html" | "htm") => Some("text/html; charset=utf-8"),
+        Some("txt") => Some("text/plain; charset=utf-8"),
+        Some("css") => Some("text/css; charset=utf-8"),
+        Some("js" | "mjs") => Some("text/javascript; charset=utf-8"),
+        Some("csv") => Some("text/csv; charset=utf-8"),
+        Some("md") => Some("text/markdown; charset=utf-8"),
+        Some("json") => Some("application/json"),
+        Some("xml") => Some("application/xml"),
+        Some("svg") => Some("image/svg+xml"),
+        _ => None,
+    }
+}
+
+fn implement_renderable(
+    item: &proc_macro2::TokenStream,
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    compiled: &CompiledTemplate,
+) -> proc_macro2::TokenStream {
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let CompiledTemplate {
+        code,
+        size_hint,
+        content_type,
+        tracking,
+    } = compiled;
 
     quote! {
         #item
 
+        #tracking
+
         impl #impl_generics magik::Renderable for #name #ty_generics #where_clause {
-            fn render(&self) -> String {
+            fn render_to(&self, magik__out: &mut dyn std::fmt::Write) -> std::fmt::Result {
                 #code
-                __hidden::magik__render(self)
+            }
+
+            fn render_escaped_to(
+                &self,
+                out: &mut dyn std::fmt::Write,
+                _escape: magik::Escape,
+            ) -> std::fmt::Result {
+                // The output of a template is already escaped by its own rules
+                self.render_to(out)
+            }
+
+            fn size_hint(&self) -> usize {
+                #size_hint
+            }
+
+            fn content_type(&self) -> Option<&'static str> {
+                #content_type
+            }
+        }
+
+        // Lets the template be rendered inside fallible templates, in lists and options
+        impl #impl_generics magik::TryRenderable for #name #ty_generics #where_clause {
+            fn try_render_to(
+                &self,
+                out: &mut dyn std::fmt::Write,
+            ) -> std::result::Result<(), magik::Error> {
+                Ok(magik::Renderable::render_to(self, out)?)
+            }
+
+            fn try_render_escaped_to(
+                &self,
+                out: &mut dyn std::fmt::Write,
+                _escape: magik::Escape,
+            ) -> std::result::Result<(), magik::Error> {
+                self.try_render_to(out)
             }
         }
 
         impl #impl_generics std::fmt::Display for #name #ty_generics #where_clause {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                use magik::Renderable;
-                write!(f, "{}", self.render())
+                magik::Renderable::render_to(self, f)
+            }
+        }
+    }
+}
+
+/// Implements `TryRenderable` for a fallible template, whose code returns
+/// `Result<(), magik::Error>`. It doesn't implement `Renderable`, so that its
+/// errors can't be ignored by rendering it inside an infallible template, nor
+/// `Display`, whose `to_string` would panic on them.
+fn implement_try_renderable(
+    item: &proc_macro2::TokenStream,
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    compiled: &CompiledTemplate,
+) -> proc_macro2::TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let CompiledTemplate {
+        code,
+        size_hint,
+        content_type,
+        tracking,
+    } = compiled;
+
+    quote! {
+        #item
+
+        #tracking
+
+        impl #impl_generics magik::TryRenderable for #name #ty_generics #where_clause {
+            fn try_render_to(
+                &self,
+                magik__out: &mut dyn std::fmt::Write,
+            ) -> std::result::Result<(), magik::Error> {
+                #code
+            }
+
+            fn try_render_escaped_to(
+                &self,
+                out: &mut dyn std::fmt::Write,
+                _escape: magik::Escape,
+            ) -> std::result::Result<(), magik::Error> {
+                // The output of a template is already escaped by its own rules
+                self.try_render_to(out)
+            }
+
+            fn try_render(&self) -> std::result::Result<String, magik::Error> {
+                let mut output = String::with_capacity({ #size_hint });
+                self.try_render_to(&mut output)?;
+                Ok(output)
+            }
+        }
+
+        // Render errors of the template give 500 responses
+        impl #impl_generics magik::ResponseBody for #name #ty_generics #where_clause {
+            fn render_body(&self) -> std::result::Result<String, magik::Error> {
+                magik::TryRenderable::try_render(self)
+            }
+
+            fn content_type(&self) -> Option<&'static str> {
+                #content_type
             }
         }
     }
-    .into()
 }
 
 struct Attributes {
-    path: Option<String>,
-    source: Option<String>,
+    path: Option<syn::LitStr>,
+    source: Option<syn::LitStr>,
     context: Option<String>,
+    escape: Option<magik_core::Escape>,
+    trim_blocks: bool,
+    delimiters: Option<(String, String)>,
+    relative_to: Option<RelativeTo>,
+    filters: Vec<syn::Ident>,
+    fallible: bool,
 }
 
-impl Parse for Attributes {
-    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        if let Ok(val) = input.parse::<syn::LitStr>() {
-            return Ok(Attributes {
-                path: None,
-                source: Some(val.value()),
-                context: None,
-            });
+impl Attributes {
+    /// Fills the options that weren't given with the ones of `defaults`, like the
+    /// attribute of an enum for the attributes of its variants.
+    fn or(self, defaults: &Attributes) -> Attributes {
+        Attributes {
+            context: self.context.or(defaults.context.clone()),
+            escape: self.escape.or(defaults.escape),
+            trim_blocks: self.trim_blocks || defaults.trim_blocks,
+            delimiters: self.delimiters.or(defaults.delimiters.clone()),
+            relative_to: self.relative_to.or(defaults.relative_to),
+            filters: [defaults.filters.clone(), self.filters].concat(),
+            fallible: defaults.fallible,
+            ..self
         }
+    }
+}
 
+impl Parse for Attributes {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let mut path = None;
         let mut source = None;
+
+        // A leading string literal is the inline source, and may be followed by other options
+        if input.peek(syn::LitStr) {
+            source = Some(input.parse::<syn::LitStr>()?);
+            if !input.is_empty() {
+                let _ = input.parse::<syn::Token![,]>()?;
+            }
+        }
+
         let mut context = None;
+        let mut escape = None;
+        let mut trim_blocks = false;
+        let mut delimiters = None;
+        let mut relative_to = None;
+        let mut filters = vec![];
+        let mut fallible = false;
 
         while !input.is_empty() {
             let key: syn::Ident = input.parse()?;
+
+            // `fallible` is a flag that can be written without a value
+            if key == "fallible" && !input.peek(syn::Token![=]) {
+                fallible = true;
+                if input.peek(syn::Token![,]) {
+                    let _ = input.parse::<syn::Token![,]>()?;
+                }
+                continue;
+            }
             let _ = input.parse::<syn::Token![=]>()?;
-            let value: syn::LitStr = input.parse()?;
 
             match key.to_string().as_str() {
                 "path" => {
@@ -105,24 +622,72 @@ impl Parse for Attributes {
                             "Cannot specify both 'path' and 'source'",
                         ));
                     }
-                    path = Some(value.value());
+                    path = Some(input.parse::<syn::LitStr>()?);
                 }
                 "source" => {
+                    if source.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            key,
+                            "The template source is already specified",
+                        ));
+                    }
                     if path.is_some() {
                         return Err(syn::Error::new_spanned(
                             key,
                             "Cannot specify both 'path' and 'source'",
                         ));
                     }
-                    source = Some(value.value());
+                    source = Some(input.parse::<syn::LitStr>()?);
                 }
                 "context" => {
-                    context = Some(value.value());
+                    context = Some(input.parse::<syn::LitStr>()?.value());
+                }
+                "escape" => {
+                    let value = input.parse::<syn::LitStr>()?;
+                    escape = Some(parse_escape(&value.value()).ok_or_else(|| {
+                        syn::Error::new_spanned(&value, "Expected 'html' or 'none' escape mode")
+                    })?);
+                }
+                "trim_blocks" => {
+                    trim_blocks = input.parse::<syn::LitBool>()?.value;
+                }
+                "fallible" => {
+                    fallible = input.parse::<syn::LitBool>()?.value;
+                }
+                "delimiters" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    let open = content.parse::<syn::LitStr>()?;
+                    let _ = content.parse::<syn::Token![,]>()?;
+                    let close = content.parse::<syn::LitStr>()?;
+
+                    if open.value().is_empty() || close.value().is_empty() {
+                        return Err(syn::Error::new_spanned(key, "Delimiters cannot be empty"));
+                    }
+                    delimiters = Some((open.value(), close.value()));
+                }
+                "filters" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    filters.extend(content.parse_terminated(syn::Ident::parse, syn::Token![,])?);
+                }
+                "relative_to" => {
+                    let value = input.parse::<syn::LitStr>()?;
+                    relative_to = match value.value().as_str() {
+                        "manifest" => Some(RelativeTo::Manifest),
+                        "file" => Some(RelativeTo::File),
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                value,
+                                "Expected 'manifest' or 'file' for relative_to",
+                            ));
+                        }
+                    };
                 }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         key,
-                        "Expected 'path', 'source', or 'context' attribute",
+                        "Expected 'path', 'source', 'context', 'escape', 'trim_blocks', 'delimiters', 'relative_to', 'filters', or 'fallible' attribute",
                     ));
                 }
             }
@@ -136,6 +701,52 @@ impl Parse for Attributes {
             path,
             source,
             context,
+            escape,
+            trim_blocks,
+            delimiters,
+            relative_to,
+            filters,
+            fallible,
+        })
+    }
+}
+
+/// Input of `render!`, the template literal followed by `name = value` bindings.
+struct RenderInput {
+    source: syn::LitStr,
+    /// `escape = "..."` option, which is not bound as a variable
+    escape: Option<magik_core::Escape>,
+    bindings: Vec<(syn::Ident, syn::Expr)>,
+}
+
+impl Parse for RenderInput {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let source = input.parse::<syn::LitStr>()?;
+        let mut escape = None;
+        let mut bindings = vec![];
+
+        while !input.is_empty() {
+            let _ = input.parse::<syn::Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+
+            let name: syn::Ident = input.parse()?;
+            let _ = input.parse::<syn::Token![=]>()?;
+            if name == "escape" {
+                let value = input.parse::<syn::LitStr>()?;
+                escape = Some(parse_escape(&value.value()).ok_or_else(|| {
+                    syn::Error::new_spanned(&value, "Expected 'html' or 'none' escape mode")
+                })?);
+                continue;
+            }
+            bindings.push((name, input.parse::<syn::Expr>()?));
+        }
+
+        Ok(RenderInput {
+            source,
+            escape,
+            bindings,
         })
     }
 }
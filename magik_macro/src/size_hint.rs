@@ -0,0 +1,303 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, Ident, Pat, Stmt};
+
+use crate::control_flow::Node;
+
+/// Builds an expression estimating the rendered size of a template: the length of its
+/// static text plus the size hints of the values it interpolates.
+///
+/// Values are only counted when they can be evaluated outside the render function:
+/// literals, fields of the context or of loop variables, and components built from them.
+/// Loops over such a value add up their body for each item, and only the longest branch
+/// of an `if` or `match` is counted. Method calls and other local variables count as 0,
+/// as do all the values of fallible templates, which may not be `Renderable`.
+pub fn compile_size_hint(nodes: &[Node], context: Option<&str>, fallible: bool) -> TokenStream {
+    let context = Ident::new(context.unwrap_or("props"), proc_macro2::Span::call_site());
+    let estimator = Estimator {
+        context: &context,
+        fallible,
+    };
+
+    let hint = estimator.nodes(nodes, &[]);
+    if hint.parts.is_empty() {
+        return hint.into_tokens();
+    }
+
+    // Components may be imported by the template's own `use` statements
+    let mut imports: Vec<syn::ItemUse> = vec![];
+    for node in nodes {
+        node.for_each_fragment(&mut |(data, _)| {
+            if let magik_core::TemplateData::Code(code) = data
+                && let Ok(block) = syn::parse_str::<syn::Block>(code)
+            {
+                for stmt in block.stmts {
+                    if let Stmt::Item(syn::Item::Use(import)) = stmt {
+                        let text = quote! { #import }.to_string();
+                        if !imports
+                            .iter()
+                            .any(|known| quote! { #known }.to_string() == text)
+                        {
+                            imports.push(import);
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    let hint = hint.into_tokens();
+    quote! {
+        #(#[allow(unused_imports)] #imports)*
+        let #context = self;
+        #hint
+    }
+}
+
+/// Static length and expressions adding up to the size hint of some nodes.
+#[derive(Default)]
+struct Hint {
+    fixed: usize,
+    parts: Vec<TokenStream>,
+}
+
+impl Hint {
+    fn add(&mut self, other: Hint) {
+        self.fixed += other.fixed;
+        self.parts.extend(other.parts);
+    }
+
+    fn into_tokens(self) -> TokenStream {
+        let Hint { fixed, parts } = self;
+        quote! { #fixed #(+ #parts)* }
+    }
+}
+
+struct Estimator<'c> {
+    context: &'c Ident,
+    fallible: bool,
+}
+
+impl Estimator<'_> {
+    /// Estimates a list of nodes, where `locals` are the loop variables in scope.
+    /// Variables declared by `let` statements shadow them for the following nodes.
+    fn nodes(&self, nodes: &[Node], locals: &[Ident]) -> Hint {
+        let mut locals = locals.to_vec();
+        let mut hint = Hint::default();
+        for node in nodes {
+            hint.add(self.node(node, &locals));
+
+            if let Node::Fragment((magik_core::TemplateData::Code(code), _)) = node
+                && let Ok(block) = syn::parse_str::<syn::Block>(code)
+            {
+                for stmt in &block.stmts {
+                    if let Stmt::Local(local) = stmt {
+                        locals = shadow(&locals, &local.pat);
+                    }
+                }
+            }
+        }
+        hint
+    }
+
+    fn node(&self, node: &Node, locals: &[Ident]) -> Hint {
+        match node {
+            Node::Fragment((magik_core::TemplateData::String(text), _)) => Hint {
+                fixed: text.len(),
+                parts: vec![],
+            },
+            Node::Fragment((magik_core::TemplateData::Code(code), _)) => self.value(code, locals),
+            Node::Loop { head, body, .. } => self.loop_body(head, body, locals),
+            Node::If {
+                branches,
+                otherwise,
+                ..
+            } => {
+                let mut hints: Vec<Hint> = branches
+                    .iter()
+                    .map(|(head, body)| {
+                        let locals = match syn::parse2::<syn::ExprIf>(quote! { #head {} }) {
+                            Ok(syn::ExprIf { cond, .. }) => match *cond {
+                                Expr::Let(binding) => shadow(locals, &binding.pat),
+                                _ => locals.to_vec(),
+                            },
+                            Err(_) => vec![],
+                        };
+                        self.nodes(body, &locals)
+                    })
+                    .collect();
+                hints.extend(otherwise.iter().map(|body| self.nodes(body, locals)));
+                self.longest(hints)
+            }
+            Node::Match { arms, .. } => {
+                let hints = arms
+                    .iter()
+                    .map(|(head, body)| {
+                        let locals =
+                            match syn::parse2::<syn::ExprMatch>(quote! { match () { #head {} } }) {
+                                Ok(syn::ExprMatch { arms, .. }) => match arms.first() {
+                                    Some(arm) => shadow(locals, &arm.pat),
+                                    None => vec![],
+                                },
+                                Err(_) => vec![],
+                            };
+                        self.nodes(body, &locals)
+                    })
+                    .collect();
+                self.longest(hints)
+            }
+            Node::Block { body, .. } | Node::Include { body, .. } => self.nodes(body, locals),
+            Node::Extends { .. } => Hint::default(),
+        }
+    }
+
+    fn value(&self, code: &str, locals: &[Ident]) -> Hint {
+        let Ok(block) = syn::parse_str::<syn::Block>(code) else {
+            return Hint::default();
+        };
+
+        match block.stmts.as_slice() {
+            [Stmt::Expr(expr, None)] if !self.fallible && self.is_readable(expr, locals) => Hint {
+                fixed: 0,
+                parts: vec![quote! { magik::Renderable::size_hint(&(#expr)) }],
+            },
+            _ => Hint::default(),
+        }
+    }
+
+    /// Estimates a `for` loop over a readable value by adding up its body for each item.
+    /// The body of other loops is counted once.
+    fn loop_body(&self, head: &TokenStream, body: &[Node], locals: &[Ident]) -> Hint {
+        let Ok(syn::ExprForLoop { pat, expr, .. }) =
+            syn::parse2::<syn::ExprForLoop>(quote! { #head {} })
+        else {
+            return self.nodes(body, locals);
+        };
+        if !self.is_iterable(&expr, locals) {
+            return self.nodes(body, locals);
+        }
+
+        let mut item_locals = locals.to_vec();
+        pattern_idents(&pat, &mut item_locals);
+
+        let item = self.nodes(body, &item_locals);
+        if item.parts.is_empty() && item.fixed == 0 {
+            return Hint::default();
+        }
+        if item.parts.is_empty() {
+            let fixed = item.fixed;
+            return Hint {
+                fixed: 0,
+                parts: vec![quote! { #fixed * std::iter::IntoIterator::into_iter(#expr).count() }],
+            };
+        }
+
+        let item = item.into_tokens();
+        Hint {
+            fixed: 0,
+            parts: vec![quote! {{
+                #[allow(unused_variables)]
+                let magik__hint = {
+                    let mut magik__hint = 0;
+                    for #pat in #expr {
+                        magik__hint += #item;
+                    }
+                    magik__hint
+                };
+                magik__hint
+            }}],
+        }
+    }
+
+    /// Keeps the longest of alternative bodies, of which a single one is rendered.
+    fn longest(&self, hints: Vec<Hint>) -> Hint {
+        if hints.iter().all(|hint| hint.parts.is_empty()) {
+            return Hint {
+                fixed: hints.iter().map(|hint| hint.fixed).max().unwrap_or(0),
+                parts: vec![],
+            };
+        }
+
+        let hints = hints.into_iter().map(Hint::into_tokens);
+        Hint {
+            fixed: 0,
+            parts: vec![quote! { 0usize #(.max(#hints))* }],
+        }
+    }
+
+    /// Checks if the expression can be evaluated outside the render function: a literal,
+    /// a field of the context or a loop variable, or a component built from them.
+    fn is_readable(&self, expr: &Expr, locals: &[Ident]) -> bool {
+        match expr {
+            Expr::Lit(_) => true,
+            // The context itself would estimate its own size hint
+            Expr::Path(path) => path
+                .path
+                .get_ident()
+                .is_some_and(|ident| locals.contains(ident)),
+            Expr::Field(field) => match field.base.as_ref() {
+                Expr::Path(path) if path.path.is_ident(self.context) => true,
+                base => self.is_readable(base, locals),
+            },
+            Expr::Reference(reference) => self.is_readable(&reference.expr, locals),
+            Expr::Paren(paren) => self.is_readable(&paren.expr, locals),
+            Expr::Struct(component) => {
+                component.rest.is_none()
+                    && component
+                        .fields
+                        .iter()
+                        .all(|field| self.is_readable(&field.expr, locals))
+            }
+            _ => false,
+        }
+    }
+
+    /// Checks if a loop iterates a readable value, directly or through `.iter()`.
+    fn is_iterable(&self, expr: &Expr, locals: &[Ident]) -> bool {
+        match expr {
+            Expr::MethodCall(call) if call.method == "iter" && call.args.is_empty() => {
+                self.is_readable(&call.receiver, locals)
+            }
+            Expr::Lit(_) | Expr::Struct(_) => false,
+            expr => self.is_readable(expr, locals),
+        }
+    }
+}
+
+/// Removes the variables bound by a pattern from the loop variables.
+fn shadow(locals: &[Ident], pat: &Pat) -> Vec<Ident> {
+    let mut bound = vec![];
+    pattern_idents(pat, &mut bound);
+    locals
+        .iter()
+        .filter(|local| !bound.contains(local))
+        .cloned()
+        .collect()
+}
+
+/// Collects the variables bound by a pattern, like `item` in `(index, item)`.
+fn pattern_idents(pat: &Pat, idents: &mut Vec<Ident>) {
+    match pat {
+        Pat::Ident(pat) => idents.push(pat.ident.clone()),
+        Pat::Tuple(tuple) => tuple
+            .elems
+            .iter()
+            .for_each(|pat| pattern_idents(pat, idents)),
+        Pat::TupleStruct(tuple) => tuple
+            .elems
+            .iter()
+            .for_each(|pat| pattern_idents(pat, idents)),
+        Pat::Struct(fields) => fields
+            .fields
+            .iter()
+            .for_each(|field| pattern_idents(&field.pat, idents)),
+        Pat::Slice(slice) => slice
+            .elems
+            .iter()
+            .for_each(|pat| pattern_idents(pat, idents)),
+        Pat::Reference(reference) => pattern_idents(&reference.pat, idents),
+        Pat::Paren(paren) => pattern_idents(&paren.pat, idents),
+        Pat::Type(typed) => pattern_idents(&typed.pat, idents),
+        _ => {}
+    }
+}
@@ -0,0 +1,173 @@
+use std::fmt::Display;
+
+use magik_core::{Location, Span};
+use syn::LitStr;
+
+/// Template text together with where it came from, used to report errors
+/// at the line and column of the template where they happened.
+pub struct TemplateSource {
+    pub text: String,
+    /// Path of the template file, `None` for inline templates
+    pub path: Option<String>,
+    /// Literal of the attribute that defined the template
+    pub lit: LitStr,
+}
+
+impl TemplateSource {
+    pub fn from_file(text: String, path: String, lit: &LitStr) -> Self {
+        TemplateSource {
+            text,
+            path: Some(path),
+            lit: lit.clone(),
+        }
+    }
+
+    pub fn inline(lit: &LitStr) -> Self {
+        TemplateSource {
+            text: lit.value(),
+            path: None,
+            lit: lit.clone(),
+        }
+    }
+
+    /// Builds an error for the given fragment of the template.
+    pub fn error(&self, span: &Span, message: impl Display) -> syn::Error {
+        let location = span.location;
+        let snippet = self.snippet(location);
+
+        match &self.path {
+            Some(path) => syn::Error::new(
+                self.lit.span(),
+                format!(
+                    "{}:{}:{}: {}\n{}",
+                    path, location.line, location.column, message, snippet
+                ),
+            ),
+            None => {
+                // Point into the string literal when the compiler supports it
+                let span = literal_offset(&self.lit.token().to_string(), span.range.start)
+                    .and_then(|start| self.lit.token().subspan(start..start + 1))
+                    .unwrap_or_else(|| self.lit.span());
+
+                syn::Error::new(
+                    span,
+                    format!(
+                        "line {}, column {}: {}\n{}",
+                        location.line, location.column, message, snippet
+                    ),
+                )
+            }
+        }
+    }
+
+    /// Converts an error of the template parser, using its location when it has one.
+    pub fn parse_error(&self, error: magik_core::Error) -> syn::Error {
+        match error.location() {
+            Some(location) => {
+                let start = offset_of(&self.text, location);
+                let span = Span {
+                    range: start..start,
+                    location,
+                };
+                self.error(&span, error.without_location())
+            }
+            None => syn::Error::new(self.lit.span(), error.to_string()),
+        }
+    }
+
+    /// Renders the line of the location with a caret below its column.
+    fn snippet(&self, location: Location) -> String {
+        let line = self.text.lines().nth(location.line - 1).unwrap_or_default();
+        let number = location.line.to_string();
+        let gutter = " ".repeat(number.len());
+        let padding: String = line
+            .chars()
+            .take(location.column - 1)
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+
+        format!("{gutter} |\n{number} | {line}\n{gutter} | {padding}^")
+    }
+}
+
+/// Byte offset of a line and column in the text.
+fn offset_of(text: &str, location: Location) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(location.line - 1)
+        .map(str::len)
+        .sum();
+
+    text[line_start..]
+        .char_indices()
+        .nth(location.column - 1)
+        .map_or(text.len(), |(offset, _)| line_start + offset)
+}
+
+/// Maps a byte offset in the value of a string literal to a byte offset in its
+/// source representation, accounting for the quotes, raw string hashes and escapes.
+fn literal_offset(repr: &str, value_offset: usize) -> Option<usize> {
+    if repr.starts_with('r') {
+        let prefix = repr.find('"')? + 1;
+        return Some(prefix + value_offset);
+    }
+
+    let mut chars = repr.char_indices().skip(1).peekable();
+    let mut value_pos = 0;
+
+    while let Some((index, ch)) = chars.next() {
+        if value_pos >= value_offset {
+            return Some(index);
+        }
+
+        if ch != '\\' {
+            value_pos += ch.len_utf8();
+            continue;
+        }
+
+        match chars.next()?.1 {
+            'x' => {
+                chars.nth(1)?;
+                value_pos += 1;
+            }
+            'u' => {
+                let mut code = String::new();
+                for (_, ch) in chars.by_ref() {
+                    match ch {
+                        '{' => {}
+                        '}' => break,
+                        ch => code.push(ch),
+                    }
+                }
+                let ch = char::from_u32(u32::from_str_radix(&code, 16).ok()?)?;
+                value_pos += ch.len_utf8();
+            }
+            '\n' => {
+                // Line continuation, skips the leading whitespace of the next line
+                while chars.next_if(|(_, ch)| ch.is_whitespace()).is_some() {}
+            }
+            _ => value_pos += 1,
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_offset() {
+        assert_eq!(literal_offset("\"abc\"", 1), Some(2));
+        assert_eq!(literal_offset("r#\"abc\"#", 1), Some(4));
+        assert_eq!(literal_offset("\"a\\\"bc\"", 2), Some(4));
+        assert_eq!(literal_offset("\"\\u{e9}x\"", 2), Some(7));
+    }
+
+    #[test]
+    fn test_offset_of() {
+        let text = "first\nsecond";
+        assert_eq!(offset_of(text, Location { line: 2, column: 3 }), 8);
+    }
+}
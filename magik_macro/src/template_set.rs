@@ -0,0 +1,210 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use magik_core::Span;
+
+use crate::{
+    control_flow::{Node, parse_nodes},
+    inheritance::extends_of,
+    source::TemplateSource,
+    utils::{TemplateLoader, parse_template},
+};
+
+/// Templates that make up the template of a struct: its own, the bases it extends and
+/// every template they include, loaded before any of them is compiled.
+pub struct TemplateSet {
+    /// Sources of the templates, starting with the struct's own
+    pub sources: Vec<TemplateSource>,
+    /// Absolute path of each template, `None` for inline templates
+    files: Vec<Option<PathBuf>>,
+    /// Index of the base template that each template extends
+    extends: Vec<Option<usize>>,
+    /// Index of the template that each path of an `extends` or `include` tag refers to
+    paths: HashMap<String, usize>,
+}
+
+impl TemplateSet {
+    /// Loads the templates referenced from the struct's template, recursively.
+    pub fn load(
+        source: TemplateSource,
+        file: Option<PathBuf>,
+        loader: &mut TemplateLoader,
+        delimiters: Option<&(String, String)>,
+    ) -> syn::Result<Self> {
+        let mut set = TemplateSet {
+            sources: vec![source],
+            files: vec![file],
+            extends: vec![None],
+            paths: HashMap::new(),
+        };
+
+        set.visit(0, &mut vec![0], loader, delimiters)?;
+        Ok(set)
+    }
+
+    fn visit(
+        &mut self,
+        index: usize,
+        stack: &mut Vec<usize>,
+        loader: &mut TemplateLoader,
+        delimiters: Option<&(String, String)>,
+    ) -> syn::Result<()> {
+        let source = &self.sources[index];
+        let (extends, includes) = {
+            let fragments = parse_template(source, delimiters)?;
+            let nodes = parse_nodes(&fragments, source)?;
+
+            let mut includes = vec![];
+            collect_includes(&nodes, &mut includes);
+            (extends_of(&nodes), includes)
+        };
+
+        let references = extends
+            .iter()
+            .map(|reference| (reference, true))
+            .chain(includes.iter().map(|reference| (reference, false)));
+
+        for ((path, span), is_extends) in references {
+            let (target, is_new) = match self.paths.get(path) {
+                Some(&target) => (target, false),
+                None => {
+                    let (source, file) = loader
+                        .load(path)
+                        .map_err(|err| self.sources[index].error(span, err))?;
+
+                    // The same file may be referenced through different paths
+                    match self
+                        .files
+                        .iter()
+                        .position(|known| known.as_ref() == Some(&file))
+                    {
+                        Some(target) => (target, false),
+                        None => {
+                            self.sources.push(source);
+                            self.files.push(Some(file));
+                            self.extends.push(None);
+                            (self.sources.len() - 1, true)
+                        }
+                    }
+                }
+            };
+            self.paths.insert(path.clone(), target);
+
+            if stack.contains(&target) {
+                let chain: Vec<&str> = stack
+                    .iter()
+                    .chain([&target])
+                    .map(|&template| self.name(template))
+                    .collect();
+                let kind = if is_extends { "inheritance" } else { "include" };
+
+                return Err(self.sources[index].error(
+                    span,
+                    format!("Template {} cycle: {}", kind, chain.join(" -> ")),
+                ));
+            }
+
+            if is_extends {
+                self.extends[index] = Some(target);
+            }
+
+            // Templates loaded before were visited right after being loaded
+            if is_new {
+                stack.push(target);
+                self.visit(target, stack, loader, delimiters)?;
+                stack.pop();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn name(&self, index: usize) -> &str {
+        self.sources[index]
+            .path
+            .as_deref()
+            .unwrap_or("inline template")
+    }
+
+    /// Indexes of the templates from the struct's own to the base at the root of
+    /// its `extends` chain.
+    pub fn chain(&self) -> Vec<usize> {
+        let mut chain = vec![0];
+        let mut current = 0;
+        while let Some(base) = self.extends[current] {
+            chain.push(base);
+            current = base;
+        }
+        chain
+    }
+
+    /// Splices the nodes of included templates into the `include` tags of a template.
+    pub fn expand_includes<'t, 'a>(
+        &'t self,
+        index: usize,
+        trees: &[Vec<Node<'t, 'a>>],
+    ) -> Vec<Node<'t, 'a>> {
+        let mut nodes = trees[index].clone();
+        self.expand_nodes(&mut nodes, trees);
+        nodes
+    }
+
+    fn expand_nodes<'t, 'a>(&'t self, nodes: &mut [Node<'t, 'a>], trees: &[Vec<Node<'t, 'a>>]) {
+        for node in nodes {
+            let children: Vec<&mut Vec<Node<'t, 'a>>> = match node {
+                Node::Include {
+                    path, body, source, ..
+                } => {
+                    // Every path was loaded along with the template referencing it
+                    let target = self.paths[path.as_str()];
+                    *body = self.expand_includes(target, trees);
+                    *source = Some(&self.sources[target]);
+                    continue;
+                }
+                Node::Fragment(_) | Node::Extends { .. } => continue,
+                Node::Loop { body, .. } | Node::Block { body, .. } => vec![body],
+                Node::If {
+                    branches,
+                    otherwise,
+                    ..
+                } => branches
+                    .iter_mut()
+                    .map(|(_, body)| body)
+                    .chain(otherwise.as_mut())
+                    .collect(),
+                Node::Match { arms, .. } => arms.iter_mut().map(|(_, body)| body).collect(),
+            };
+
+            for body in children {
+                self.expand_nodes(body, trees);
+            }
+        }
+    }
+}
+
+/// Collects the paths of the `include` tags in a tree along with their spans.
+fn collect_includes(nodes: &[Node], includes: &mut Vec<(String, Span)>) {
+    for node in nodes {
+        match node {
+            Node::Include { path, opener, .. } => includes.push((path.clone(), opener.1.clone())),
+            Node::Fragment(_) | Node::Extends { .. } => {}
+            Node::Loop { body, .. } | Node::Block { body, .. } => collect_includes(body, includes),
+            Node::If {
+                branches,
+                otherwise,
+                ..
+            } => {
+                for (_, body) in branches {
+                    collect_includes(body, includes);
+                }
+                if let Some(body) = otherwise {
+                    collect_includes(body, includes);
+                }
+            }
+            Node::Match { arms, .. } => {
+                for (_, body) in arms {
+                    collect_includes(body, includes);
+                }
+            }
+        }
+    }
+}
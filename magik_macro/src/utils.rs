@@ -1,20 +1,122 @@
-use std::{borrow::Cow, vec};
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
 
-use magik::Error;
+use magik_core::{Error, HtmlContext, Span};
 use quote::{quote, quote_spanned};
-use syn::{Ident, ItemStruct, Stmt, parse_quote_spanned, spanned::Spanned};
+use syn::{LitStr, Stmt, parse_quote_spanned, spanned::Spanned};
 
-use crate::is_block_returning_value;
+use crate::{
+    config::Config,
+    control_flow::{Node, is_control_tag},
+    filters::desugar_filters,
+    is_block_returning_value,
+    source::TemplateSource,
+};
 
-pub fn read_template_file(path: &str) -> Result<String, magik::Error> {
-    let basedir = std::env::current_dir()
-        .map_err(|_| Error::TemplateReadError(Cow::Borrowed("Cannot access current directory")))?;
-    let full_path = basedir.join(path);
+/// Directory that template paths are resolved against.
+#[derive(Clone, Copy, Default)]
+pub enum RelativeTo {
+    /// Root of the crate being compiled, where its `Cargo.toml` lives
+    #[default]
+    Manifest,
+    /// Directory of the source file that declares the template
+    File,
+}
+
+/// Root directory of the crate being compiled, where its `Cargo.toml` lives.
+pub fn crate_root() -> Result<PathBuf, Error> {
+    let current_dir = current_dir()?;
+
+    Ok(match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(root) => current_dir.join(root),
+        None => current_dir,
+    })
+}
 
+fn current_dir() -> Result<PathBuf, Error> {
+    std::env::current_dir()
+        .map_err(|_| Error::TemplateReadError(Cow::Borrowed("Cannot access current directory")))
+}
+
+/// Resolves a template path into an absolute path, so it doesn't depend on the
+/// directory the compiler was started from. Paths relative to the crate are searched
+/// in the template directories of its configuration.
+pub fn resolve_template_path(
+    path: &str,
+    relative_to: RelativeTo,
+    config: &Config,
+    root: &Path,
+) -> Result<PathBuf, Error> {
+    match relative_to {
+        RelativeTo::Manifest => config.find(root, path),
+        RelativeTo::File => {
+            let basedir = proc_macro::Span::call_site()
+                .local_file()
+                .and_then(|file| file.parent().map(Path::to_path_buf))
+                .ok_or(Error::TemplateReadError(Cow::Borrowed(
+                    "Cannot find the source file of the template",
+                )))?;
+
+            Ok(current_dir()?.join(basedir).join(path))
+        }
+    }
+}
+
+/// Loads the template files of a struct, keeping track of them so that the generated
+/// code can make Cargo rebuild the crate when they change.
+pub struct TemplateLoader<'c> {
+    pub config: &'c Config,
+    pub root: PathBuf,
+    pub relative_to: RelativeTo,
+    /// Literal of the attribute declaring the template, where errors are reported
+    pub lit: LitStr,
+    files: Vec<PathBuf>,
+}
+
+impl<'c> TemplateLoader<'c> {
+    pub fn new(config: &'c Config, root: PathBuf, relative_to: RelativeTo, lit: LitStr) -> Self {
+        TemplateLoader {
+            config,
+            root,
+            relative_to,
+            lit,
+            files: vec![],
+        }
+    }
+
+    /// Reads a template, returning it along with the absolute path of its file.
+    pub fn load(&mut self, path: &str) -> Result<(TemplateSource, PathBuf), Error> {
+        let full_path = resolve_template_path(path, self.relative_to, self.config, &self.root)?;
+        let text = read_template_file(&full_path)?;
+
+        self.files.push(full_path.clone());
+        Ok((
+            TemplateSource::from_file(text, path.to_string(), &self.lit),
+            full_path,
+        ))
+    }
+
+    /// Items including every loaded file and the configuration, so Cargo tracks them
+    /// as inputs of the crate.
+    pub fn tracking(&self) -> proc_macro2::TokenStream {
+        let files = self
+            .files
+            .iter()
+            .chain(&self.config.file)
+            .map(|file| file.to_string_lossy());
+        quote! {
+            #(const _: &[u8] = include_bytes!(#files);)*
+        }
+    }
+}
+
+pub fn read_template_file(full_path: &Path) -> Result<String, magik_core::Error> {
     if !full_path.exists() {
         return Err(Error::TemplateNotFound(Cow::Owned(format!(
             "Template file does not exist at: {}",
-            path
+            full_path.display()
         ))));
     }
 
@@ -22,72 +124,259 @@ pub fn read_template_file(path: &str) -> Result<String, magik::Error> {
         Ok(content) => Ok(content),
         Err(_) => Err(Error::TemplateReadError(Cow::Owned(format!(
             "Failed to read template file at: {}",
-            path
+            full_path.display()
         )))),
     }
 }
 
-pub fn parse_template(input: &str) -> Result<Vec<magik::TemplateData>, magik::Error> {
-    let parser = magik::Parser::new(input);
-    parser.collect::<Result<Vec<magik::TemplateData>, Error>>()
+pub fn parse_template<'a>(
+    source: &'a TemplateSource,
+    delimiters: Option<&'a (String, String)>,
+) -> syn::Result<Vec<(magik_core::TemplateData<'a>, Span)>> {
+    let mut parser = magik_core::Parser::new(&source.text);
+    if let Some((open, close)) = delimiters {
+        parser = parser.with_delimiters(open, close);
+    }
+
+    parser
+        .spanned()
+        .collect::<Result<Vec<_>, Error>>()
+        .map_err(|err| source.parse_error(err))
 }
 
-pub fn compile_template(
-    tmp: &Vec<magik::TemplateData>,
-    struct_item: &ItemStruct,
-    context: Option<&str>,
-) -> proc_macro2::TokenStream {
-    let mut quotes = vec![];
+/// Settings of a template and the HTML context reached while compiling its nodes.
+struct NodeCompiler {
+    fallible: bool,
+    escape: magik_core::Escape,
+    /// Context of the markup before the next node, followed in HTML templates to pick
+    /// the escaping of each value
+    html: Option<HtmlContext>,
+    /// Names of the filters declared by the crate, desugared like the built-in ones
+    filters: Vec<String>,
+}
+
+impl NodeCompiler {
+    /// Compiles the alternative bodies of a block, like the branches of an `if`, which
+    /// all start from the current context and must end in contexts that merge. `otherwise`
+    /// tells whether a missing `else` is an empty alternative.
+    fn compile_alternatives(
+        &mut self,
+        bodies: Vec<&[Node]>,
+        otherwise: bool,
+        source: &TemplateSource,
+        opener: &Span,
+    ) -> syn::Result<Vec<Vec<proc_macro2::TokenStream>>> {
+        let start = self.html.clone();
+        let mut end = (!otherwise).then(|| start.clone());
+        let mut quotes = vec![];
 
-    let capacity = tmp.len();
+        for body in bodies {
+            self.html = start.clone();
+            quotes.push(self.compile_nodes(body, source)?);
 
-    quotes.push(quote! {
-        let mut magik__result = Vec::with_capacity(#capacity);
-    });
+            end = match end {
+                None => Some(self.html.clone()),
+                Some(end) => Some(self.merge(end, source, opener)?),
+            };
+        }
 
-    for data in tmp {
+        self.html = end.unwrap_or(start);
+        Ok(quotes)
+    }
+
+    /// Merges the context where a body ends with the one of another path through the
+    /// same block, failing when they could read the following markup differently.
+    fn merge(
+        &self,
+        other: Option<HtmlContext>,
+        source: &TemplateSource,
+        opener: &Span,
+    ) -> syn::Result<Option<HtmlContext>> {
+        let (Some(current), Some(other)) = (&self.html, &other) else {
+            return Ok(None);
+        };
+
+        current.merge(other).map(Some).ok_or_else(|| {
+            source.error(
+                opener,
+                "The paths through this block end in different HTML contexts, \
+                 like inside and outside of a tag",
+            )
+        })
+    }
+
+    fn compile_nodes(
+        &mut self,
+        nodes: &[Node],
+        source: &TemplateSource,
+    ) -> syn::Result<Vec<proc_macro2::TokenStream>> {
+        let mut quotes = vec![];
+
+        for node in nodes {
+            match node {
+                Node::Fragment((data, span)) => {
+                    self.compile_fragment(data, span, source, &mut quotes)?
+                }
+                Node::Loop { head, body, opener } => {
+                    // The body may run any number of times, starting where it ends
+                    let start = self.html.clone();
+                    let body = self.compile_nodes(body, source)?;
+                    if self.merge(start.clone(), source, &opener.1)? != start {
+                        return Err(source.error(
+                            &opener.1,
+                            "The body of this loop ends in a different HTML context than it starts",
+                        ));
+                    }
+                    self.html = start;
+
+                    quotes.push(quote! {
+                        #head { #(#body)* }
+                    });
+                }
+                Node::If {
+                    branches,
+                    otherwise,
+                    opener,
+                } => {
+                    let bodies = branches
+                        .iter()
+                        .map(|(_, body)| body.as_slice())
+                        .chain(otherwise.as_deref())
+                        .collect();
+                    let mut bodies =
+                        self.compile_alternatives(bodies, otherwise.is_some(), source, &opener.1)?;
+                    let otherwise = match otherwise {
+                        Some(_) => {
+                            let body = bodies.pop().unwrap_or_default();
+                            quote! { else { #(#body)* } }
+                        }
+                        None => quote! {},
+                    };
+
+                    let branch_quotes = branches
+                        .iter()
+                        .zip(bodies)
+                        .map(|((head, _), body)| quote! { #head { #(#body)* } });
+
+                    quotes.push(quote! {
+                        #(#branch_quotes)else* #otherwise
+                    });
+                }
+                Node::Match { head, arms, opener } => {
+                    let bodies = arms.iter().map(|(_, body)| body.as_slice()).collect();
+                    let bodies = self.compile_alternatives(bodies, true, source, &opener.1)?;
+                    let arm_quotes = arms
+                        .iter()
+                        .zip(bodies)
+                        .map(|((pattern, _), body)| quote! { #pattern { #(#body)* } });
+
+                    quotes.push(quote! {
+                        #head { #(#arm_quotes)* }
+                    });
+                }
+                Node::Block {
+                    body,
+                    source: block_source,
+                    ..
+                } => {
+                    // The body may come from a template extending this one
+                    let body = self.compile_nodes(body, block_source)?;
+                    quotes.push(quote! {
+                        { #(#body)* }
+                    });
+                }
+                Node::Include {
+                    body,
+                    source: Some(include_source),
+                    ..
+                } => {
+                    let body = self.compile_nodes(body, include_source)?;
+                    quotes.push(quote! {
+                        { #(#body)* }
+                    });
+                }
+                Node::Include {
+                    source: None,
+                    opener,
+                    ..
+                } => {
+                    return Err(source.error(
+                        &opener.1,
+                        "`include` is only supported in templates declared with `#[template]`",
+                    ));
+                }
+                Node::Extends { opener, .. } => {
+                    return Err(source.error(
+                        &opener.1,
+                        "`extends` must be the first tag of a template declared with `#[template]`",
+                    ));
+                }
+            }
+        }
+
+        Ok(quotes)
+    }
+
+    fn compile_fragment(
+        &mut self,
+        data: &magik_core::TemplateData,
+        span: &Span,
+        source: &TemplateSource,
+        quotes: &mut Vec<proc_macro2::TokenStream>,
+    ) -> syn::Result<()> {
         match data {
-            magik::TemplateData::String(html) => {
+            magik_core::TemplateData::String(html) => {
                 let html_str = html.as_ref();
+                if let Some(context) = &mut self.html {
+                    context.feed(html_str);
+                }
+
                 quotes.push(quote! {
-                   magik__result.push(std::borrow::Cow::Borrowed(#html_str));
+                   magik__out.write_str(#html_str)?;
                 });
             }
-            magik::TemplateData::Code(code) => {
-                let code_str = code.as_ref();
-                let code: syn::Block = match syn::parse_str(code_str) {
-                    Ok(expr) => expr,
-                    Err(err) => {
-                        return syn::Error::new_spanned(
-                            code_str,
-                            format!("Error parsing code: {}", err),
-                        )
-                        .to_compile_error();
-                    }
-                };
+            magik_core::TemplateData::Code(code) => {
+                let desugared = desugar_filters(code, &self.filters);
+                let code_str = desugared.as_deref().unwrap_or(code);
+                let code: syn::Block = syn::parse_str(code_str)
+                    .map_err(|err| source.error(span, format!("Error parsing code: {}", err)))?;
 
                 if code.stmts.is_empty() {
-                    continue; // Skip empty code blocks
+                    return Ok(()); // Skip empty code blocks
                 }
 
                 // call a function to check if block returns a value
                 if is_block_returning_value(&code) {
+                    let escape = match &self.html {
+                        Some(context) => context.escape().map_err(|err| source.error(span, err))?,
+                        None => self.escape,
+                    };
+                    let escape = escape_tokens(escape);
+
                     let mut stmts = code.stmts.clone();
                     let last_stmt = match stmts.pop() {
                         Some(stmt) => stmt,
                         None => {
-                            return syn::Error::new_spanned(
+                            return Err(syn::Error::new_spanned(
                                 &code,
                                 "Empty code block marked as returning value",
-                            )
-                            .to_compile_error();
+                            ));
                         }
                     };
 
                     let new_last = match last_stmt {
+                        // Values of fallible templates may implement `TryRenderable`
+                        // instead of `Renderable`, picked by method resolution
+                        Stmt::Expr(expr, None) if self.fallible => Stmt::Expr(
+                            syn::Expr::MethodCall(parse_quote_spanned! {expr.span() =>
+                                (&magik::__private::Dispatch(&(#expr)))
+                                    .dispatch_to(magik__out, #escape)
+                            }),
+                            None,
+                        ),
                         Stmt::Expr(expr, None) => Stmt::Expr(
                             syn::Expr::Call(parse_quote_spanned! {expr.span() =>
-                                magik__render_and_validate(&#expr)
+                                magik__render_and_validate(&(#expr), magik__out, #escape)
                             }),
                             None,
                         ),
@@ -104,9 +393,7 @@ pub fn compile_template(
                     };
 
                     quotes.push(quote_spanned! {
-                        code.span() => magik__result.push(std::borrow::Cow::Owned(
-                            #new_block
-                        ));
+                        code.span() => #new_block?;
                     });
                 } else {
                     code.stmts.iter().for_each(|stmt| {
@@ -117,28 +404,196 @@ pub fn compile_template(
                 }
             }
         }
+
+        Ok(())
+    }
+}
+
+/// Path to the escaping strategy in the generated code.
+pub fn escape_tokens(escape: magik_core::Escape) -> proc_macro2::TokenStream {
+    match escape {
+        magik_core::Escape::None => quote! { magik::Escape::None },
+        magik_core::Escape::Html => quote! { magik::Escape::Html },
+        magik_core::Escape::Attribute => quote! { magik::Escape::Attribute },
+        magik_core::Escape::Url => quote! { magik::Escape::Url },
+        magik_core::Escape::Js => quote! { magik::Escape::Js },
+        magik_core::Escape::Css => quote! { magik::Escape::Css },
+    }
+}
+
+/// Removes the lines holding only statement blocks, like `{{ use crate::Button; }}`,
+/// or control flow tags, so that they leave no blank lines in the output.
+pub fn trim_blocks(tmp: &mut [(magik_core::TemplateData, Span)]) {
+    let is_statement: Vec<bool> = tmp
+        .iter()
+        .map(|(data, _)| match data {
+            magik_core::TemplateData::Code(code) => {
+                is_control_tag(code)
+                    || syn::parse_str::<syn::Block>(code)
+                        .is_ok_and(|block| !is_block_returning_value(&block))
+            }
+            magik_core::TemplateData::String(_) => false,
+        })
+        .collect();
+
+    // Bytes to cut from the start and the end of each string, computed on the
+    // original strings so that blocks on consecutive lines share their newline
+    let mut cuts: Vec<(usize, usize)> = tmp
+        .iter()
+        .map(|(data, _)| (0, data.as_str().len()))
+        .collect();
+
+    for index in (0..tmp.len()).filter(|index| is_statement[*index]) {
+        let before = line_start_cuts(tmp[..index].iter().enumerate().rev(), &is_statement);
+        let after = line_end_cuts(tmp.iter().enumerate().skip(index + 1), &is_statement);
+
+        if let (Some(before), Some(after)) = (before, after) {
+            for (i, end) in before {
+                cuts[i].1 = cuts[i].1.min(end);
+            }
+            for (i, start) in after {
+                cuts[i].0 = cuts[i].0.max(start);
+            }
+        }
     }
 
-    let struct_name = &struct_item.ident;
-    let generics = &struct_item.generics;
-    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    for ((data, span), (start, end)) in tmp.iter_mut().zip(cuts) {
+        let magik_core::TemplateData::String(text) = data else {
+            continue;
+        };
+
+        let (start, end) = (start.min(end), end);
+        if (start, end) == (0, text.len()) {
+            continue;
+        }
 
-    let context = Ident::new(context.unwrap_or("props"), proc_macro2::Span::call_site());
+        for ch in text[..start].chars() {
+            if ch == '\n' {
+                span.location.line += 1;
+                span.location.column = 1;
+            } else {
+                span.location.column += 1;
+            }
+        }
+        span.range = span.range.start + start..span.range.start + end;
 
-    quote! {
-        mod __hidden {
-            use magik::Choosable;
-            use super::#struct_name;
+        *text = Cow::Owned(text[start..end].to_string());
+    }
+}
+
+/// Walks back from a statement block to the start of its line, returning for each
+/// string the offset where its trailing whitespace starts, or `None` if the line
+/// holds anything other than whitespace and statement blocks.
+fn line_start_cuts<'a, 'b: 'a>(
+    fragments: impl Iterator<Item = (usize, &'a (magik_core::TemplateData<'b>, Span))>,
+    is_statement: &[bool],
+) -> Option<Vec<(usize, usize)>> {
+    let mut cuts = vec![];
 
-            #[inline(always)]
-            fn magik__render_and_validate<'a, T: magik::Renderable>(value: &'a T) -> String {
-                value.render()
+    for (index, (data, _)) in fragments {
+        let magik_core::TemplateData::String(text) = data else {
+            if is_statement[index] {
+                continue;
             }
+            return None;
+        };
+
+        let line_start = text.rfind('\n').map_or(0, |newline| newline + 1);
+        if !text[line_start..].chars().all(|ch| ch == ' ' || ch == '\t') {
+            return None;
+        }
+
+        cuts.push((index, line_start));
+        if line_start > 0 {
+            break;
+        }
+    }
+
+    Some(cuts)
+}
+
+/// Walks forward from a statement block to the end of its line, returning for each
+/// string the offset right after its leading whitespace and newline, or `None` if the
+/// line holds anything other than whitespace and statement blocks.
+fn line_end_cuts<'a, 'b: 'a>(
+    fragments: impl Iterator<Item = (usize, &'a (magik_core::TemplateData<'b>, Span))>,
+    is_statement: &[bool],
+) -> Option<Vec<(usize, usize)>> {
+    let mut cuts = vec![];
 
-            pub fn magik__render #impl_generics(#context: &#struct_name #ty_generics) -> String #where_clause {
-                #(#quotes)*
-                magik__result.concat()
+    for (index, (data, _)) in fragments {
+        let magik_core::TemplateData::String(text) = data else {
+            if is_statement[index] {
+                continue;
             }
+            return None;
+        };
+
+        let line_end = text.find('\n').map_or(text.len(), |newline| newline + 1);
+        if !text[..line_end]
+            .trim_end_matches('\n')
+            .chars()
+            .all(|ch| ch == ' ' || ch == '\t' || ch == '\r')
+        {
+            return None;
+        }
+
+        cuts.push((index, line_end));
+        if line_end < text.len() || text.ends_with('\n') {
+            break;
         }
     }
+
+    Some(cuts)
+}
+
+/// Builds the body of a render function writing into `magik__out`, where `bindings`
+/// declare the variables the template can read, like `let props = self;`. The body of a
+/// fallible template returns `Result<(), magik::Error>` instead of `std::fmt::Result`.
+/// With HTML escaping, each value is escaped for its position in the markup.
+pub fn compile_template(
+    nodes: &[Node],
+    source: &TemplateSource,
+    bindings: proc_macro2::TokenStream,
+    escape: magik_core::Escape,
+    filters: &[String],
+    fallible: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut compiler = NodeCompiler {
+        fallible,
+        escape,
+        html: (escape == magik_core::Escape::Html).then(HtmlContext::default),
+        filters: filters.to_vec(),
+    };
+    let quotes = compiler.compile_nodes(nodes, source)?;
+
+    if fallible {
+        return Ok(quote! {
+            use magik::{Choosable as _, IntoRenderable as _};
+            use magik::__private::{RenderableDispatch as _, TryRenderableDispatch as _};
+
+            #bindings
+            #(#quotes)*
+            Ok(())
+        });
+    }
+
+    // The body is emitted inside a function, so it has no items of its own
+    // that could collide with other templates declared in the same scope
+    Ok(quote! {
+        use magik::{Choosable as _, IntoRenderable as _};
+
+        #[inline(always)]
+        fn magik__render_and_validate<T: magik::Renderable>(
+            value: &T,
+            out: &mut dyn std::fmt::Write,
+            escape: magik::Escape,
+        ) -> std::fmt::Result {
+            value.render_escaped_to(out, escape)
+        }
+
+        #bindings
+        #(#quotes)*
+        Ok(())
+    })
 }
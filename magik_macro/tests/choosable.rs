@@ -0,0 +1,25 @@
+use magik::{Renderable, template_str};
+
+#[template_str(
+    "{{ props.user.choose_with(|name| format!(\"Hi {}\", name), || \"Sign in\".to_string()) }} \
+     {{ magik::select(&props.role, [(\"admin\", \"Administrator\")], \"Reader\") }}"
+)]
+struct Menu<'a> {
+    user: Option<&'a str>,
+    role: &'a str,
+}
+
+#[test]
+fn test_choose_in_template() {
+    let menu = Menu {
+        user: Some("Ada"),
+        role: "admin",
+    };
+    assert_eq!(menu.render(), "Hi Ada Administrator");
+
+    let menu = Menu {
+        user: None,
+        role: "editor",
+    };
+    assert_eq!(menu.render(), "Sign in Reader");
+}
@@ -0,0 +1,86 @@
+use magik::Renderable;
+use magik_macro::template;
+
+enum Status {
+    Active,
+    Away(u32),
+    Offline,
+}
+
+#[template(
+    source = r#"<ul>
+{{ for (index, item) in props.items.iter().enumerate() }}
+    <li>{{ index }}: {{ *item }}</li>
+{{ end }}
+</ul>"#,
+    trim_blocks = true
+)]
+struct List<'a> {
+    items: Vec<&'a str>,
+}
+
+#[template(
+    source = "{{ if props.score >= 90 }}A{{ else if props.score >= 50 }}B{{ else }}C{{ end }}"
+)]
+struct Grade {
+    score: u32,
+}
+
+#[template(source = "{{ if let Some(name) = props.name }}Hi {{ name }}{{ end }}!")]
+struct Greeting<'a> {
+    name: Option<&'a str>,
+}
+
+#[template(source = r#"{{ match props.status }}
+    {{ Status::Active => }}online
+    {{ Status::Away(minutes) if minutes > 60 => }}away for hours
+    {{ Status::Away(minutes) => }}away for {{ minutes }} minutes
+    {{ _ => }}offline
+{{ end }}"#)]
+struct Presence {
+    status: Status,
+}
+
+#[template(
+    source = "{{ let mut stack = props.stack.clone(); }}{{ while let Some(top) = stack.pop() }}[{{ top }}]{{ end }}"
+)]
+struct Stack {
+    stack: Vec<u8>,
+}
+
+#[test]
+fn test_for_loop() {
+    let list = List {
+        items: vec!["a", "b"],
+    };
+    assert_eq!(
+        list.render(),
+        "<ul>\n    <li>0: a</li>\n    <li>1: b</li>\n</ul>"
+    );
+}
+
+#[test]
+fn test_if_else() {
+    assert_eq!(Grade { score: 95 }.render(), "A");
+    assert_eq!(Grade { score: 60 }.render(), "B");
+    assert_eq!(Grade { score: 10 }.render(), "C");
+    assert_eq!(Greeting { name: Some("Ann") }.render(), "Hi Ann!");
+    assert_eq!(Greeting { name: None }.render(), "!");
+}
+
+#[test]
+fn test_match_arms() {
+    let render = |status| Presence { status }.render().trim().to_string();
+    assert_eq!(render(Status::Active), "online");
+    assert_eq!(render(Status::Away(90)), "away for hours");
+    assert_eq!(render(Status::Away(5)), "away for 5 minutes");
+    assert_eq!(render(Status::Offline), "offline");
+}
+
+#[test]
+fn test_while_let() {
+    let stack = Stack {
+        stack: vec![1, 2, 3],
+    };
+    assert_eq!(stack.render(), "[3][2][1]");
+}
@@ -0,0 +1,51 @@
+use magik::Renderable;
+use magik_macro::template;
+
+#[template(source = "<div id=\"app\">\\{{ message }} {{ props.count }}</div>")]
+struct VueMount {
+    count: u32,
+}
+
+#[template(source = "C:\\\\{{ props.dir }}\\Users")]
+struct WindowsPath<'a> {
+    dir: &'a str,
+}
+
+#[template(source = "{% raw %}{{#each items}}{{this}}{{/each}}{% endraw %} {{ props.name }}")]
+struct Handlebars<'a> {
+    name: &'a str,
+}
+
+#[template(
+    source = "{{ title }}: <%= props.title %>{# kept #}<# removed #>",
+    delimiters = ("<%=", "%>")
+)]
+struct Custom<'a> {
+    title: &'a str,
+}
+
+#[test]
+fn test_escaped_delimiter() {
+    assert_eq!(
+        VueMount { count: 2 }.render(),
+        "<div id=\"app\">{{ message }} 2</div>"
+    );
+}
+
+#[test]
+fn test_raw_region() {
+    assert_eq!(
+        Handlebars { name: "list" }.render(),
+        "{{#each items}}{{this}}{{/each}} list"
+    );
+}
+
+#[test]
+fn test_custom_delimiters() {
+    assert_eq!(Custom { title: "Hi" }.render(), "{{ title }}: Hi{# kept #}");
+}
+
+#[test]
+fn test_backslash_before_block() {
+    assert_eq!(WindowsPath { dir: "Home" }.render(), "C:\\Home\\Users");
+}
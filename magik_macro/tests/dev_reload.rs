@@ -0,0 +1,79 @@
+#![cfg(all(feature = "dev-reload", debug_assertions))]
+
+use std::path::PathBuf;
+
+use magik::Renderable;
+use magik_macro::template;
+
+#[template(path = "tests/templates/reload/page.tmp")]
+struct Page<'a> {
+    title: &'a str,
+}
+
+#[template(path = "tests/templates/reload/trimmed.tmp", trim_blocks = true)]
+struct Trimmed<'a> {
+    title: &'a str,
+}
+
+/// Template file edited by a test, restored to its compiled contents when dropped.
+struct Edited {
+    path: PathBuf,
+    original: String,
+}
+
+impl Edited {
+    fn new(path: &str) -> Self {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(path);
+        let original = std::fs::read_to_string(&path).unwrap();
+        Edited { path, original }
+    }
+
+    fn write(&self, contents: &str) {
+        std::fs::write(&self.path, contents).unwrap();
+    }
+}
+
+impl Drop for Edited {
+    fn drop(&mut self) {
+        std::fs::write(&self.path, &self.original).unwrap();
+    }
+}
+
+#[test]
+fn test_reload_edited_template() {
+    let file = Edited::new("tests/templates/reload/page.tmp");
+    let page = Page { title: "Home" };
+    assert_eq!(page.render(), "<h1>Home</h1>");
+
+    file.write("<h2>{{ props.title }}</h2>");
+    assert_eq!(page.render(), "<h2>Home</h2>");
+
+    file.write("<h2>{{ props.title }}!</h2>");
+    assert_eq!(page.render(), "<h2>Home!</h2>");
+
+    // Anything but `props.field` blocks renders the compiled template
+    file.write("{{ include \"../partials/header.tmp\" }}");
+    assert_eq!(page.render(), "<h1>Home</h1>");
+
+    file.write("{{ props.title | upper }}");
+    assert_eq!(page.render(), "<h1>Home</h1>");
+
+    file.write("{{ props.title.len() }}");
+    assert_eq!(page.render(), "<h1>Home</h1>");
+}
+
+#[test]
+fn test_reload_trimmed_template() {
+    let file = Edited::new("tests/templates/reload/trimmed.tmp");
+    let page = Trimmed { title: "Home" };
+    assert_eq!(page.render(), "Hi, Home");
+
+    file.write("Hey, {{ props.title }}");
+    assert_eq!(page.render(), "Hey, Home");
+
+    file.write("{{ }}\nHey, {{ props.title }}");
+    assert_eq!(page.render(), "Hi, Home");
+
+    file.write("{{ let greeting = \"Hey\"; }}\n{{ greeting }}, {{ props.title }}");
+    assert_eq!(page.render(), "Hi, Home");
+}
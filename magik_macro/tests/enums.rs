@@ -0,0 +1,47 @@
+use magik::{Renderable, TryRenderable};
+use magik_macro::template;
+
+#[template(escape = "html")]
+enum Badge<'a> {
+    #[template(source = r#"<span class="badge active">Active</span>"#)]
+    Active,
+    #[template(source = r#"<span class="badge suspended">{{ reason }}</span>"#)]
+    Suspended { reason: &'a str },
+    #[template(source = "<span>{{ _0 }}: {{ _1 }}</span>", escape = "none")]
+    Custom(&'a str, u32),
+}
+
+#[template(fallible)]
+enum Notice {
+    #[template(source = "{{ props.check()?; }}ok")]
+    Checked { valid: bool },
+}
+
+impl Notice {
+    fn check(&self) -> Result<(), magik::Error> {
+        match self {
+            Notice::Checked { valid: true } => Ok(()),
+            Notice::Checked { valid: false } => Err(magik::Error::RenderError("invalid".into())),
+        }
+    }
+}
+
+#[test]
+fn test_enum_variants() {
+    assert_eq!(
+        Badge::Active.render(),
+        r#"<span class="badge active">Active</span>"#
+    );
+    assert_eq!(
+        Badge::Suspended { reason: "<spam>" }.render(),
+        r#"<span class="badge suspended">&lt;spam&gt;</span>"#
+    );
+    assert_eq!(Badge::Custom("<b>", 3).render(), "<span><b>: 3</span>");
+    assert_eq!(Badge::Active.to_string(), Badge::Active.render());
+}
+
+#[test]
+fn test_fallible_enum() {
+    assert_eq!(Notice::Checked { valid: true }.try_render().unwrap(), "ok");
+    assert!(Notice::Checked { valid: false }.try_render().is_err());
+}
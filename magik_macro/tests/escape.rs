@@ -0,0 +1,164 @@
+use magik::Renderable;
+use magik_macro::template;
+
+#[template(source = "<b>{{ props.text }}</b>", escape = "html")]
+struct Bold<'a> {
+    text: &'a str,
+}
+
+#[template(source = "<i>{{ props.text }}</i>")]
+struct Italic<'a> {
+    text: &'a str,
+}
+
+#[template(
+    source = "<p>{{ Bold { text: props.text } }} {{ props.text }} {{ magik::Raw(props.text) }}</p>",
+    escape = "html"
+)]
+struct Paragraph<'a> {
+    text: &'a str,
+}
+
+#[template(
+    source = r#"<a href="{{ props.url }}" title='{{ props.text }}' onclick="show('{{ props.text }}')">{{ props.text }}</a>
+<a href="/search?q={{ props.text }}" style="color: {{ props.color }}">x</a>
+<script>let text = "{{ props.text }}";</script>"#,
+    escape = "html"
+)]
+struct Link<'a> {
+    url: &'a str,
+    text: &'a str,
+    color: &'a str,
+}
+
+#[template(
+    source = r#"<a href="{{ props.url | escape }}" title="{{ props.text | escape }}">{{ props.text | escape }}</a>
+<script>let text = "{{ props.text | escape }}";</script>"#,
+    escape = "html"
+)]
+struct FilteredLink<'a> {
+    url: &'a str,
+    text: &'a str,
+}
+
+#[template(
+    source = r#"<input type="checkbox"{{ if props.checked }} checked{{ end }} value="{{ props.value }}">"#,
+    escape = "html"
+)]
+struct Checkbox<'a> {
+    checked: bool,
+    value: &'a str,
+}
+
+#[test]
+fn test_escape_html() {
+    let bold = Bold {
+        text: "<Tom & Jerry>",
+    };
+    assert_eq!(bold.render(), "<b>&lt;Tom &amp; Jerry&gt;</b>");
+}
+
+#[test]
+fn test_escape_none_by_default() {
+    let italic = Italic { text: "<br>" };
+    assert_eq!(italic.render(), "<i><br></i>");
+}
+
+#[test]
+fn test_nested_component_and_raw() {
+    let paragraph = Paragraph { text: "<br>" };
+    assert_eq!(
+        paragraph.render(),
+        "<p><b>&lt;br&gt;</b> &lt;br&gt; <br></p>"
+    );
+}
+
+#[test]
+fn test_escape_by_context() {
+    let link = Link {
+        url: "javascript:alert(1)",
+        text: "it's <b>",
+        color: "red;}",
+    };
+    assert_eq!(
+        link.render(),
+        r##"<a href="#" title='it&#x27;s &lt;b&gt;' onclick="show('it\u0027s \u003Cb\u003E')">it&#x27;s &lt;b&gt;</a>
+<a href="/search?q=it&#x27;s &lt;b&gt;" style="color: red\3B \7D ">x</a>
+<script>let text = "it\u0027s \u003Cb\u003E";</script>"##
+    );
+
+    let link = Link {
+        url: "https://example.com/?a=1&b=2",
+        text: "",
+        color: "#fff",
+    };
+    assert!(
+        link.render()
+            .starts_with(r#"<a href="https://example.com/?a&#x3D;1&amp;b&#x3D;2" "#)
+    );
+    assert!(link.render().contains(r#"style="color: #fff""#));
+}
+
+#[test]
+fn test_optional_attribute() {
+    let checkbox = Checkbox {
+        checked: true,
+        value: "a=\"b\"",
+    };
+    assert_eq!(
+        checkbox.render(),
+        r#"<input type="checkbox" checked value="a&#x3D;&quot;b&quot;">"#
+    );
+}
+
+#[test]
+fn test_escape_filter_by_context() {
+    let link = FilteredLink {
+        url: "javascript:alert(1)",
+        text: "a\\ \"<b>",
+    };
+    assert_eq!(
+        link.render(),
+        r##"<a href="#" title="a\ &quot;&lt;b&gt;">a\ &quot;&lt;b&gt;</a>
+<script>let text = "a\u005C \u0022\u003Cb\u003E";</script>"##
+    );
+
+    let link = FilteredLink {
+        url: "/users?id=1",
+        text: "",
+    };
+    assert!(link.render().starts_with(r#"<a href="/users?id&#x3D;1" "#));
+}
+
+mod names {
+    use magik::Renderable;
+    use magik_macro::template;
+
+    /// Type of the crate named like `magik::Raw`
+    pub struct Raw(pub &'static str);
+
+    impl Renderable for Raw {
+        fn render_to(&self, out: &mut dyn std::fmt::Write) -> std::fmt::Result {
+            write!(out, "[{}]", self.0)
+        }
+    }
+
+    #[template(source = "{{ Raw(props.text) }}", escape = "html")]
+    pub struct Local {
+        pub text: &'static str,
+    }
+
+    #[template(source = "{{ use magik::Raw; }}{{ Raw(props.text) }}", escape = "html")]
+    pub struct Imported {
+        pub text: &'static str,
+    }
+}
+
+#[test]
+fn test_template_scope_keeps_user_names() {
+    let local = names::Local { text: "<b>" };
+    assert_eq!(local.render(), "[&lt;b&gt;]");
+
+    let imported = names::Imported { text: "<b>" };
+    assert_eq!(imported.render(), "<b>");
+}
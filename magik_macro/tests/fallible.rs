@@ -0,0 +1,88 @@
+use magik::{Error, TryRenderable};
+use magik_macro::template;
+
+#[derive(Debug)]
+struct DbError(&'static str);
+
+impl From<DbError> for Error {
+    fn from(err: DbError) -> Self {
+        Error::RenderError(err.0.into())
+    }
+}
+
+fn find_user(id: u32) -> Result<&'static str, DbError> {
+    match id {
+        1 => Ok("<Ada>"),
+        _ => Err(DbError("user not found")),
+    }
+}
+
+#[template(source = "<li>{{ props.name }}</li>")]
+struct Item<'a> {
+    name: &'a str,
+}
+
+#[template(
+    source = "{{ let name = find_user(props.id)?; }}<p>{{ name }}</p>\
+              {{ Item { name } }}{{ props.orders.clone() }}",
+    escape = "html",
+    fallible
+)]
+struct Profile {
+    id: u32,
+    orders: Vec<Result<u32, String>>,
+}
+
+#[template(
+    source = "<main>{{ Profile { id: props.id, orders: vec![] } }}</main>",
+    fallible
+)]
+struct Page {
+    id: u32,
+}
+
+#[test]
+fn test_fallible_template() {
+    let profile = Profile {
+        id: 1,
+        orders: vec![Ok(3), Ok(5)],
+    };
+    assert_eq!(
+        profile.try_render().unwrap(),
+        "<p>&lt;Ada&gt;</p><li><Ada></li>3\n5"
+    );
+}
+
+#[test]
+fn test_question_mark_propagates() {
+    let profile = Profile {
+        id: 2,
+        orders: vec![],
+    };
+    let error = profile.try_render().unwrap_err();
+    assert_eq!(error.to_string(), "Render error: user not found");
+}
+
+#[test]
+fn test_failed_value_and_child() {
+    let profile = Profile {
+        id: 1,
+        orders: vec![Ok(3), Err("timeout".into())],
+    };
+    assert!(profile.try_render().is_err());
+
+    assert_eq!(
+        Page { id: 1 }.try_render().unwrap(),
+        "<main><p>&lt;Ada&gt;</p><li><Ada></li></main>"
+    );
+    assert!(Page { id: 2 }.try_render().is_err());
+}
+
+#[test]
+fn test_error_reaches_the_writer_caller() {
+    let mut output = String::new();
+    let error = Page { id: 2 }.try_render_to(&mut output).unwrap_err();
+
+    assert_eq!(error.to_string(), "Render error: user not found");
+    assert_eq!(output, "<main>");
+}
@@ -0,0 +1,56 @@
+use magik::Renderable;
+use magik_macro::template;
+
+pub trait CurrencyFilter {
+    fn currency(&self) -> String;
+}
+
+impl CurrencyFilter for f64 {
+    fn currency(&self) -> String {
+        format!("${:.2}", self)
+    }
+}
+
+#[template(
+    source = "{{ props.name | trim | upper | truncate(5) }} owes {{ props.total | currency }} \
+              for {{ props.count | number }} {{ props.count | pluralize(\"item\", \"items\") }}",
+    escape = "html",
+    filters = (currency)
+)]
+struct Invoice<'a> {
+    name: &'a str,
+    total: f64,
+    count: u32,
+}
+
+#[template(
+    source = "{{ props.nickname | default(\"Anonymous\") }} {{ props.bio | escape }} \
+              {{ props.flags | 0b100 }} {{ let mask = 0b010; }}{{ props.flags | mask }} {{ props.tags.iter().map(|tag| tag.len() | 1).sum::<usize>() }}"
+)]
+struct Profile<'a> {
+    nickname: Option<&'a str>,
+    bio: &'a str,
+    flags: u8,
+    tags: Vec<&'a str>,
+}
+
+#[test]
+fn test_filter_pipeline() {
+    let invoice = Invoice {
+        name: "  Grace Hopper ",
+        total: 12.5,
+        count: 1200,
+    };
+    assert_eq!(invoice.render(), "GRACE... owes $12.50 for 1,200 items");
+}
+
+#[test]
+fn test_filters_keep_rust_operators() {
+    let profile = Profile {
+        nickname: None,
+        bio: "<b>hi</b>",
+        flags: 0b001,
+        tags: vec!["ab", "c"],
+    };
+    assert_eq!(profile.render(), "Anonymous &lt;b&gt;hi&lt;/b&gt; 5 3 4");
+}
@@ -0,0 +1,47 @@
+use magik::Renderable;
+use magik_macro::template_str;
+
+#[template_str(r#"{{ include "tests/templates/partials/header.tmp" }}<p>{{ props.body }}</p>"#)]
+struct Article<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+#[template_str(
+    r#"<ul>{{ for &item in props.items.iter() }}{{ include "tests/templates/partials/item.tmp" }}{{ end }}</ul>"#
+)]
+struct List {
+    items: Vec<u32>,
+}
+
+#[template_str(
+    r#"{{ extends "tests/templates/page.tmp" }}{{ block content }}{{ props.body }}{{ end }}"#
+)]
+struct Page<'a> {
+    title: &'a str,
+    body: &'a str,
+}
+
+#[test]
+fn test_include_shares_context() {
+    let article = Article {
+        title: "News",
+        body: "Hello",
+    };
+    assert_eq!(article.render(), "<h1>News</h1><p>Hello</p>");
+}
+
+#[test]
+fn test_include_shares_local_bindings() {
+    let list = List { items: vec![1, 2] };
+    assert_eq!(list.render(), "<ul><li>1</li><li>2</li></ul>");
+}
+
+#[test]
+fn test_include_in_base_template() {
+    let page = Page {
+        title: "Home",
+        body: "Welcome",
+    };
+    assert_eq!(page.render(), "<h1>Home</h1><main>Welcome</main>");
+}
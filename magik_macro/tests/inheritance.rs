@@ -0,0 +1,61 @@
+use magik::Renderable;
+use magik_macro::{template, template_str};
+
+#[template(path = "tests/templates/layout.tmp")]
+struct Layout<'a> {
+    footer: &'a str,
+}
+
+#[template_str(
+    r#"{{ extends "tests/templates/layout.tmp" }}
+{{ block title }}{{ props.title }}{{ end }}
+{{ block content }}<p>{{ props.body }}</p>{{ end }}"#
+)]
+struct Page<'a> {
+    title: &'a str,
+    body: &'a str,
+    footer: &'a str,
+}
+
+#[template_str(
+    r#"{{ extends "tests/templates/section.tmp" }}
+{{ use std::fmt::Write; }}
+{{ block section }}{{ props.items.join(", ") }}{{ end }}
+{{ block footer }}{{ end }}"#
+)]
+struct Nested {
+    items: Vec<&'static str>,
+}
+
+#[test]
+fn test_base_renders_defaults() {
+    let layout = Layout { footer: "2025" };
+    assert_eq!(
+        layout.render(),
+        "<title>Magik</title>\n<main></main>\n<footer>2025</footer>"
+    );
+}
+
+#[test]
+fn test_extends_overrides_blocks() {
+    let page = Page {
+        title: "Home",
+        body: "Welcome",
+        footer: "2025",
+    };
+    assert_eq!(
+        page.render(),
+        "<title>Home</title>\n<main><p>Welcome</p></main>\n<footer>2025</footer>"
+    );
+}
+
+#[test]
+fn test_extends_chain() {
+    let nested = Nested {
+        items: vec!["a", "b"],
+    };
+    assert_eq!(
+        nested.render(),
+        "<title>Magik</title>\n<main><section>a, b</section></main>\n"
+    );
+}
@@ -0,0 +1,65 @@
+use magik::{Renderable, render, template_str};
+
+#[template_str("Hello, {{ props.name }}!")]
+struct Greeting<'a> {
+    name: &'a str,
+}
+
+#[template_str("<b>{{ page.title }}</b>", context = "page", escape = "html")]
+struct Title<'a> {
+    title: &'a str,
+}
+
+#[template_str("<% props.count %> items", delimiters = ("<%", "%>"))]
+struct Counter {
+    count: usize,
+}
+
+#[test]
+fn test_template_str() {
+    assert_eq!(Greeting { name: "Magik" }.render(), "Hello, Magik!");
+}
+
+#[test]
+fn test_template_str_options() {
+    let title = Title {
+        title: "<Tom & Jerry>",
+    };
+    assert_eq!(title.render(), "<b>&lt;Tom &amp; Jerry&gt;</b>");
+    assert_eq!(Counter { count: 3 }.render(), "3 items");
+}
+
+#[test]
+fn test_render_bindings() {
+    let output = render!(
+        "{{ greeting }}, {{ name }}!",
+        greeting = "Hello",
+        name = "Magik"
+    );
+    assert_eq!(output, "Hello, Magik!");
+}
+
+#[test]
+fn test_render_escape_option() {
+    let name = "<Tom & Jerry>";
+    assert_eq!(
+        render!("<b>{{ name }}</b>", escape = "html"),
+        "<b>&lt;Tom &amp; Jerry&gt;</b>"
+    );
+    assert_eq!(
+        render!("<a title=\"{{ title }}\">", escape = "html", title = "a\"b"),
+        "<a title=\"a&quot;b\">"
+    );
+    assert_eq!(render!("<b>{{ name }}</b>"), "<b><Tom & Jerry></b>");
+}
+
+#[test]
+fn test_render_captures_scope() {
+    let items = vec![1, 2, 3];
+    let output = render!("{{ for item in &items }}[{{ *item }}]{{ end }}",);
+    assert_eq!(output, "[1][2][3]");
+    assert_eq!(
+        render!("{{ Greeting { name: \"Magik\" } }}"),
+        "Hello, Magik!"
+    );
+}
@@ -0,0 +1,20 @@
+use magik::{Renderable, template_str};
+
+#[template_str(
+    "<ul>{{ magik::each(&props.tags, |tag, info| (\"<li>\", *tag, info.last.choose(\"\", \",\"), \"</li>\")) }}</ul> \
+     {{ magik::join(&props.tags, \" | \") }} {{ props.tags.iter().map(|tag| tag.len()).into_renderable() }}"
+)]
+struct Tags<'a> {
+    tags: Vec<&'a str>,
+}
+
+#[test]
+fn test_collection_helpers() {
+    let tags = Tags {
+        tags: vec!["rust", "web"],
+    };
+    assert_eq!(
+        tags.render(),
+        "<ul><li>rust,</li><li>web</li></ul> rust | web 4\n3"
+    );
+}
@@ -0,0 +1,22 @@
+use magik::Renderable;
+use magik_macro::template;
+
+#[template(path = "tests/templates/greeting.tmp")]
+struct ManifestRelative<'a> {
+    name: &'a str,
+}
+
+#[template(path = "templates/greeting.tmp", relative_to = "file")]
+struct FileRelative<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_path_relative_to_manifest() {
+    assert_eq!(ManifestRelative { name: "Magik" }.render(), "Hello, Magik!");
+}
+
+#[test]
+fn test_path_relative_to_file() {
+    assert_eq!(FileRelative { name: "Magik" }.render(), "Hello, Magik!");
+}
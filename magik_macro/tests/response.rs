@@ -0,0 +1,136 @@
+use actix_web::{App, test as actix_test, web};
+use axum::{Router, body::Body, http::Request, routing::get};
+use magik::{Error, Response};
+use magik_macro::template;
+use tower::ServiceExt;
+
+#[template(source = "<h1>{{ props.title }}</h1>", escape = "html")]
+struct Page {
+    title: &'static str,
+}
+
+#[template(path = "tests/templates/user.json")]
+struct User {
+    name: &'static str,
+}
+
+#[template(source = "Total: {{ props.total()? }}", fallible)]
+struct Invoice {
+    amounts: Vec<u32>,
+}
+
+impl Invoice {
+    fn total(&self) -> Result<u32, Error> {
+        self.amounts
+            .iter()
+            .try_fold(0u32, |total, amount| total.checked_add(*amount))
+            .ok_or(Error::RenderError("Total overflows".into()))
+    }
+}
+
+#[test]
+fn test_content_type() {
+    assert_eq!(
+        Response::new(Page { title: "Home" }).content_type(),
+        "text/html; charset=utf-8"
+    );
+    assert_eq!(
+        Response::new(User { name: "Ada" }).content_type(),
+        "application/json"
+    );
+    assert_eq!(
+        Response::new(Invoice { amounts: vec![] }).content_type(),
+        "text/plain; charset=utf-8"
+    );
+}
+
+#[test]
+fn test_http_response() {
+    let response: axum::http::Response<String> = Response::new(Invoice {
+        amounts: vec![1, 2],
+    })
+    .into();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.body(), "Total: 3");
+
+    let response: axum::http::Response<String> = Response::new(Invoice {
+        amounts: vec![u32::MAX, 1],
+    })
+    .into();
+    assert_eq!(response.status(), 500);
+
+    let response: axum::http::Response<String> = Response::new(Page { title: "Home" })
+        .with_content_type("text/html\nX-Injected: 1")
+        .into();
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "text/plain; charset=utf-8"
+    );
+}
+
+#[tokio::test]
+async fn test_axum_response() {
+    let app = Router::new()
+        .route(
+            "/",
+            get(|| async { Response::new(Page { title: "<Home>" }) }),
+        )
+        .route(
+            "/invoice",
+            get(|| async {
+                Response::new(Invoice {
+                    amounts: vec![u32::MAX, 1],
+                })
+            }),
+        );
+
+    let request = Request::get("/").body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "text/html; charset=utf-8"
+    );
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(body, "<h1>&lt;Home&gt;</h1>");
+
+    let request = Request::get("/invoice").body(Body::empty()).unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), 500);
+}
+
+#[actix_web::test]
+async fn test_actix_web_response() {
+    let app = actix_test::init_service(
+        App::new()
+            .route(
+                "/",
+                web::get().to(|| async { Response::new(User { name: "Ada" }) }),
+            )
+            .route(
+                "/invoice",
+                web::get().to(|| async {
+                    Response::new(Invoice {
+                        amounts: vec![u32::MAX, 1],
+                    })
+                }),
+            ),
+    )
+    .await;
+
+    let request = actix_test::TestRequest::get().uri("/").to_request();
+    let response = actix_test::call_service(&app, request).await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let body = actix_test::read_body(response).await;
+    assert_eq!(body, "{\"name\": \"Ada\"}\n");
+
+    let request = actix_test::TestRequest::get().uri("/invoice").to_request();
+    let response = actix_test::call_service(&app, request).await;
+    assert_eq!(response.status(), 500);
+}
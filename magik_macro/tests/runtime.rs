@@ -0,0 +1,44 @@
+use magik::runtime::{Context, Template};
+use magik_macro::MagikContext;
+
+#[derive(MagikContext)]
+struct Order {
+    id: u32,
+    total: f64,
+}
+
+#[derive(MagikContext)]
+struct Email<'a> {
+    name: &'a str,
+    vip: bool,
+    orders: Vec<Order>,
+}
+
+#[test]
+fn test_render_derived_context() {
+    let template = Template::compile(
+        "Hello {{ name }}{{ if vip }} (VIP){{ end }}!\n\
+         {{ for order in orders }}#{{ order.id }}: {{ order.total }}\n{{ end }}",
+    )
+    .unwrap();
+
+    let email = Email {
+        name: "Ada",
+        vip: true,
+        orders: vec![Order { id: 1, total: 9.5 }, Order { id: 2, total: 20.0 }],
+    };
+
+    assert_eq!(
+        template.render(&Context::from_value(&email)).unwrap(),
+        "Hello Ada (VIP)!\n#1: 9.5\n#2: 20\n"
+    );
+}
+
+#[test]
+fn test_render_error_location() {
+    let template = Template::compile("Hi\n  {{ order.missing }}").unwrap();
+    let context = Context::new().with("order", Order { id: 1, total: 0.0 });
+
+    let error = template.render(&context).unwrap_err();
+    assert_eq!(error.to_string(), "2:3: Render error: No field 'missing'");
+}
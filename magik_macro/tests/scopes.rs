@@ -0,0 +1,41 @@
+use magik::Renderable;
+use magik_macro::template;
+
+mod components {
+    use magik_macro::template;
+
+    #[template(source = "<b>{{ props.text }}</b>")]
+    pub struct Bold<'a> {
+        pub text: &'a str,
+    }
+
+    #[template(source = "<p>{{ Bold { text: props.text } }}</p>")]
+    pub struct Paragraph<'a> {
+        pub text: &'a str,
+    }
+}
+
+#[template(source = "{{ use crate::components::Paragraph; }}{{ Paragraph { text: props.text } }}")]
+struct Page<'a> {
+    text: &'a str,
+}
+
+#[test]
+fn test_templates_in_nested_module() {
+    let page = Page { text: "Hello" };
+    assert_eq!(page.render(), "<p><b>Hello</b></p>");
+}
+
+#[test]
+fn test_templates_in_function_body() {
+    #[template(source = "[{{ props.0 }}]")]
+    struct Tag(&'static str);
+
+    #[template(source = "{{ Tag(props.name) }} {{ Tag(\"local\") }}")]
+    struct Labels {
+        name: &'static str,
+    }
+
+    let labels = Labels { name: "rust" };
+    assert_eq!(labels.render(), "[rust] [local]");
+}
@@ -0,0 +1,75 @@
+use magik::Renderable;
+use magik_macro::template;
+
+#[template(source = "Hello, {{ props.name }}! You have {{ props.count }} messages.")]
+struct Greeting<'a> {
+    name: &'a str,
+    count: u32,
+}
+
+#[template(source = "{{ let name = props.name.to_uppercase(); }}Hi {{ name }}")]
+struct Shout<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_size_hint_counts_static_text_and_fields() {
+    let greeting = Greeting {
+        name: "Alice",
+        count: 12,
+    };
+    let output = greeting.render();
+    assert_eq!(greeting.size_hint(), output.len());
+}
+
+#[test]
+fn test_size_hint_skips_local_values() {
+    let shout = Shout { name: "bob" };
+    assert_eq!(shout.size_hint(), "Hi ".len());
+    assert_eq!(shout.render(), "Hi BOB");
+}
+
+#[template(source = "<li>{{ props.name }}</li>")]
+struct Item<'a> {
+    name: &'a str,
+}
+
+#[template(
+    source = "<ul>{{ for user in &props.users }}<li>{{ user.name }}</li>{{ end }}</ul>\
+              {{ for _ in props.users.iter() }}-{{ end }}{{ Item { name: props.title } }}"
+)]
+struct UserList<'a> {
+    title: &'a str,
+    users: Vec<User<'a>>,
+}
+
+struct User<'a> {
+    name: &'a str,
+}
+
+#[template(source = "{{ if props.long }}a long branch{{ else }}short{{ end }}\
+              {{ for name in &props.names }}{{ let name = name.len(); }}{{ name }}{{ end }}")]
+struct Branches {
+    long: bool,
+    names: Vec<String>,
+}
+
+#[test]
+fn test_size_hint_counts_loops_and_components() {
+    let list = UserList {
+        title: "Team",
+        users: vec![User { name: "Ada" }, User { name: "Grace" }],
+    };
+    assert_eq!(list.size_hint(), list.render().len());
+}
+
+#[test]
+fn test_size_hint_keeps_longest_branch() {
+    let branches = Branches {
+        long: true,
+        names: vec!["abc".into(), "de".into()],
+    };
+    // The lengths bound by `let` shadow the loop variable and aren't counted
+    assert_eq!(branches.size_hint(), "a long branch".len());
+    assert_eq!(branches.render(), "a long branch32");
+}
@@ -0,0 +1,27 @@
+use magik::Renderable;
+use magik_macro::template;
+
+#[template(source = "<ul>\n    {{- props.item -}}\n</ul>")]
+struct Markers<'a> {
+    item: &'a str,
+}
+
+#[template(
+    source = "{{ let upper = props.name.to_uppercase(); }}\n{{ let lower = props.name.to_lowercase(); }}\nName: {{ upper }}\n    {{ let count = props.name.len(); }}\nLength: {{ count }} {{ let _unused = (); }}\n{{ lower }}",
+    trim_blocks = true
+)]
+struct Report<'a> {
+    name: &'a str,
+}
+
+#[test]
+fn test_trim_markers() {
+    let markers = Markers { item: "<li>x</li>" };
+    assert_eq!(markers.render(), "<ul><li>x</li></ul>");
+}
+
+#[test]
+fn test_trim_blocks() {
+    let report = Report { name: "Magik" };
+    assert_eq!(report.render(), "Name: MAGIK\nLength: 5 \nmagik");
+}